@@ -0,0 +1,31 @@
+//! Serve recorded pages for running the app without network access; see
+//! `tests/support/standin.rs`.
+
+#[path = "../tests/support/standin.rs"]
+mod standin;
+
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: standin FIXTURES [ADDRESS]";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+
+    let root = match args.next() {
+        Some(r) => PathBuf::from(r),
+        None => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:8080".into());
+
+    if let Err(e) = standin::StandIn::bind(root, &address).await {
+        eprintln!("unable to listen on {}: {}", address, e);
+        std::process::exit(1);
+    }
+
+    std::future::pending::<()>().await;
+}
@@ -1,99 +1,560 @@
+pub mod backend;
+mod backoff;
 pub mod errors;
+mod limit;
+mod queue;
+mod session;
 
 use futures_channel::oneshot;
 
-use labrat::client::Response;
+use labrat::client::{ClientError, Response};
 use labrat::keys::{
-    CommentReplyKey, FavKey, JournalKey, SubmissionsKey, ViewKey,
+    CommentReplyKey, FavKey, GalleryKey, JournalKey, SubmissionsKey, UserKey,
+    ViewKey,
 };
+use labrat::resources::gallery::Gallery;
 use labrat::resources::journal::Journal;
 use labrat::resources::msg::others::Others;
 use labrat::resources::msg::submissions::Submissions;
+use labrat::resources::user::User;
 use labrat::resources::view::View;
 
 use reqwest::header::HeaderValue;
 
+use self::backend::{Backend, Fake, Site};
+use self::backoff::Class;
 use self::errors::login::LoginError;
 use self::errors::request::RequestError;
+use self::limit::Limiter;
+use self::queue::Queue;
+use self::session::{Page, Session};
 
 use snafu::ResultExt;
 
-use std::convert::Infallible;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, watch, Semaphore};
 
-type Error = labrat::client::RequestError<Infallible>;
-type ResponseResult<T, E = Error> = std::result::Result<Response<T>, E>;
-type ResponseSender<T> = oneshot::Sender<ResponseResult<T>>;
+type Error = backend::Error;
+/// Outcome of a request, along with how many times it was attempted.
+type Attempted<T> = (Result<T, Error>, u32);
+type ResponseSender<T> = oneshot::Sender<Attempted<Response<T>>>;
+type ActionSender = oneshot::Sender<Attempted<()>>;
 
 #[derive(Debug)]
 enum Message {
     Stop,
-    Replace(labrat::client::Client, oneshot::Sender<()>),
+    /// Switch to a backend using the given cookies, or none to log out.
+    Replace(
+        Option<HeaderValue>,
+        oneshot::Sender<Result<(), ClientError>>,
+    ),
+    Request(Priority, Request),
+}
 
+#[derive(Debug)]
+enum Request {
     Journal(JournalKey, ResponseSender<Journal>),
     View(ViewKey, ResponseSender<View>),
-    Reply(CommentReplyKey, String, oneshot::Sender<Result<(), Error>>),
+    User(UserKey, ResponseSender<User>),
+    Gallery(GalleryKey, ResponseSender<Gallery>),
+    Reply(CommentReplyKey, String, ActionSender),
     Fav(FavKey, ResponseSender<View>),
     Unfav(FavKey, ResponseSender<View>),
     Others(ResponseSender<Others>),
     Submissions(SubmissionsKey, ResponseSender<Submissions>),
-    ClearSubmissions(Vec<ViewKey>, oneshot::Sender<Result<(), Error>>),
+    ClearSubmissions(Vec<ViewKey>, ActionSender),
+}
+
+impl Request {
+    async fn execute(self, session: Arc<Session>, shared: &Shared) {
+        match self {
+            Request::Journal(key, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    async move { s.backend.journal(key).await }
+                };
+                respond(reply, shared.send(session, true, request)).await;
+            }
+
+            Request::View(key, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    async move { s.backend.view(key).await }
+                };
+                respond(reply, shared.send(session, true, request)).await;
+            }
+
+            Request::User(key, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    async move { s.backend.user(key).await }
+                };
+                respond(reply, shared.send(session, true, request)).await;
+            }
+
+            Request::Gallery(key, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    async move { s.backend.gallery(key).await }
+                };
+                respond(reply, shared.send(session, true, request)).await;
+            }
+
+            Request::Reply(key, text, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    let text = text.clone();
+                    async move { s.backend.reply(key, text).await }
+                };
+                respond(reply, shared.send(session, false, request)).await;
+            }
+
+            Request::Fav(key, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    async move { s.backend.fav(key).await }
+                };
+                respond(reply, shared.send(session, false, request)).await;
+            }
+
+            Request::Unfav(key, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    async move { s.backend.unfav(key).await }
+                };
+                respond(reply, shared.send(session, false, request)).await;
+            }
+
+            Request::Others(reply) => {
+                let request =
+                    |s: Arc<Session>| async move { s.backend.others().await };
+                respond(reply, shared.send(session, true, request)).await;
+            }
+
+            Request::Submissions(key, reply) => {
+                let request = |s: Arc<Session>| {
+                    let key = key.clone();
+                    async move { s.backend.submissions(key).await }
+                };
+                respond(reply, shared.send(session, true, request)).await;
+            }
+
+            Request::ClearSubmissions(keys, reply) => {
+                let request = |s: Arc<Session>| {
+                    let keys = keys.clone();
+                    async move { s.backend.clear_submissions(keys).await }
+                };
+                respond(reply, shared.send(session, false, request)).await;
+            }
+        }
+    }
 }
 
+/// Changes in the bridge's state that the UI might want to show.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The server asked us to slow down, and requests are being held back
+    /// until at least the given instant.
+    RateLimited { until: Instant },
+
+    /// Requests are going through again after being rate limited.
+    Resumed,
+
+    /// The server stopped recognizing our cookies. Requests are held back
+    /// until the client is replaced by logging in again.
+    SessionExpired,
+}
+
+/// State shared between the bridge loop and the requests it starts.
+#[derive(Debug)]
+struct Shared {
+    config: Config,
+    limiter: Limiter,
+    events: broadcast::Sender<Event>,
+
+    sessions: watch::Receiver<Arc<Session>>,
+    expired: AtomicBool,
+}
+
+impl Shared {
+    fn is_expired(&self) -> bool {
+        self.expired.load(Ordering::SeqCst)
+    }
+
+    /// Wait until `expired` is replaced, returning `None` if the bridge
+    /// stops first.
+    async fn renewed(&self, expired: &Arc<Session>) -> Option<Arc<Session>> {
+        if !self.expired.swap(true, Ordering::SeqCst) {
+            self.events.send(Event::SessionExpired).ok();
+        }
+
+        let mut sessions = self.sessions.clone();
+
+        loop {
+            {
+                let current = sessions.borrow();
+                if !Arc::ptr_eq(&current, expired) {
+                    return Some(current.clone());
+                }
+            }
+
+            if sessions.changed().await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// Run `request` once the rate limit allows, retrying up to
+    /// `max_retries` times while the server asks us to slow down.
+    ///
+    /// Other transient failures, and overloaded servers, are only retried
+    /// for `idempotent` requests, since a mutation may have gone through
+    /// before the failure.
+    ///
+    /// If the response shows that `session` has been logged out, waits for
+    /// a new session and replays the request with it.
+    async fn send<T, F, Fut>(
+        &self,
+        mut session: Arc<Session>,
+        idempotent: bool,
+        mut request: F,
+    ) -> Attempted<T>
+    where
+        T: Page,
+        F: FnMut(Arc<Session>) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempts = 0;
+        let mut throttled = 0;
+
+        loop {
+            self.limiter.acquire().await;
+            attempts += 1;
+
+            let result = request(session.clone()).await;
+
+            if let Ok(ref page) = result {
+                if session.authenticated && !page.logged_in() {
+                    match self.renewed(&session).await {
+                        Some(renewed) => {
+                            session = renewed;
+                            continue;
+                        }
+                        None => return (result, attempts),
+                    }
+                }
+            }
+
+            let class = match result {
+                Err(ref e) => backoff::classify(e),
+                Ok(_) => Class::Fatal,
+            };
+
+            let throttling =
+                class == Class::RateLimited || class == Class::Unavailable;
+
+            if !throttling && self.limiter.resume() {
+                self.events.send(Event::Resumed).ok();
+            }
+
+            let error = match result {
+                Ok(value) => return (Ok(value), attempts),
+                Err(e) => e,
+            };
+
+            match class {
+                Class::RateLimited | Class::Unavailable => {
+                    throttled += 1;
+
+                    // Wait as long as the server asked, if it said.
+                    let delay = error.retry_after().unwrap_or_else(|| {
+                        backoff::delay(
+                            throttled,
+                            self.config.backoff_base,
+                            self.config.backoff_max,
+                        )
+                    });
+
+                    // Hold back everything else even if this request isn't
+                    // retried.
+                    let until = self.limiter.pause(delay);
+                    self.events.send(Event::RateLimited { until }).ok();
+
+                    // A refused request is safe to retry even if it's a
+                    // mutation, but an overloaded server may have handled
+                    // it before failing.
+                    let retry = idempotent || class == Class::RateLimited;
+
+                    if !retry || throttled > self.config.max_retries {
+                        return (Err(error), attempts);
+                    }
+                }
+
+                Class::Transient
+                    if idempotent
+                        && attempts - throttled <= self.config.max_retries =>
+                {
+                    let delay = backoff::delay(
+                        attempts - throttled,
+                        self.config.retry_base,
+                        self.config.backoff_max,
+                    );
+
+                    tokio::time::sleep(delay).await;
+                }
+
+                _ => return (Err(error), attempts),
+            }
+        }
+    }
+}
+
+/// Order in which the bridge starts queued requests.
+///
+/// All queued `User` requests are started before any `Background` ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Something the user explicitly asked for and is waiting on.
+    User,
+
+    /// Prefetching and other work nobody is actively waiting on.
+    Background,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of requests in flight at once.
+    pub max_concurrent: usize,
+
+    /// Sustained number of requests started per second, or zero for no
+    /// limit.
+    pub requests_per_second: f64,
+
+    /// Number of requests that can be started at once after being idle.
+    pub burst: u32,
+
+    /// First delay after the server asks us to slow down, unless it says
+    /// how long to wait with `Retry-After`.
+    pub backoff_base: Duration,
+
+    /// Longest delay between retries when the server keeps asking us to
+    /// slow down without saying for how long.
+    pub backoff_max: Duration,
+
+    /// How many times a read that failed for a transient reason (timeouts,
+    /// dropped connections, server errors) is retried, and how many times
+    /// any request is retried after the server asks us to slow down.
+    pub max_retries: u32,
+
+    /// First delay before retrying a transient failure.
+    pub retry_base: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            requests_per_second: 2.,
+            burst: 5,
+            backoff_base: Duration::from_secs(2),
+            backoff_max: Duration::from_secs(300),
+            max_retries: 3,
+            retry_base: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Handle for sending requests to the bridge thread.
+///
+/// Every request method returns a future that resolves once the bridge has
+/// a response. Dropping that future before it resolves cancels the request,
+/// aborting it in the bridge thread if it is already in flight.
 #[derive(Debug, Clone)]
 pub struct Client {
     sender: mpsc::UnboundedSender<Message>,
+    events: broadcast::Sender<Event>,
+    priority: Priority,
 }
 
 impl Client {
     async fn recv<T>(
         &self,
-        receiver: oneshot::Receiver<ResponseResult<T>>,
+        receiver: oneshot::Receiver<Attempted<Response<T>>>,
     ) -> Result<T, RequestError> {
-        let resp = receiver
-            .await
-            .map_err(|_| RequestError::Exited)?
-            .context(errors::request::Request)?;
+        let (result, attempts) =
+            receiver.await.map_err(|_| RequestError::Exited)?;
+        let resp = result.context(errors::request::Request { attempts })?;
 
         // TODO: Update notification counts and such.
 
         Ok(resp.page)
     }
 
+    async fn request<T, F>(
+        &self,
+        priority: Priority,
+        make: F,
+    ) -> Result<T, RequestError>
+    where
+        F: FnOnce(ResponseSender<T>) -> Request,
+    {
+        let (reply, recv) = oneshot::channel();
+
+        self.sender
+            .send(Message::Request(priority, make(reply)))
+            .map_err(|_| RequestError::Exited)?;
+
+        Ok(self.recv(recv).await?)
+    }
+
+    async fn action<F>(&self, make: F) -> Result<(), RequestError>
+    where
+        F: FnOnce(ActionSender) -> Request,
+    {
+        let (reply, recv) = oneshot::channel();
+
+        // Actions are always user initiated.
+        self.sender
+            .send(Message::Request(Priority::User, make(reply)))
+            .map_err(|_| RequestError::Exited)?;
+
+        let (result, attempts) =
+            recv.await.map_err(|_| RequestError::Exited)?;
+        result.context(errors::request::Request { attempts })?;
+
+        Ok(())
+    }
+
+    /// A client whose reads are queued behind user initiated requests.
+    pub fn background(&self) -> Self {
+        Self {
+            priority: Priority::Background,
+            ..self.clone()
+        }
+    }
+
+    pub fn events(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
     pub fn stop(&self) {
         self.sender.send(Message::Stop).ok();
     }
 
+    /// Replace the bridge's client with one using `cookies`.
+    ///
+    /// Requests held back because the previous session expired are replayed
+    /// with the new client.
     pub async fn login(&self, cookies: &str) -> Result<(), LoginError> {
-        let value = HeaderValue::from_str(cookies)?;
+        let mut value = HeaderValue::from_str(cookies)?;
 
-        let client = labrat::client::Client::with_cookies(value)
-            .context(errors::login::Client)?;
+        // Keeps the cookies out of `Debug` output, and so out of the logs.
+        value.set_sensitive(true);
 
+        self.replace(Some(value)).await
+    }
+
+    /// Replace the bridge's client with one that isn't logged in.
+    pub async fn logout(&self) -> Result<(), LoginError> {
+        self.replace(None).await
+    }
+
+    async fn replace(
+        &self,
+        cookies: Option<HeaderValue>,
+    ) -> Result<(), LoginError> {
         let (reply, recv) = oneshot::channel();
 
         self.sender
-            .send(Message::Replace(client, reply))
+            .send(Message::Replace(cookies, reply))
             .map_err(|_| LoginError::Exited)?;
 
-        recv.await.map_err(|_| LoginError::Exited)?;
+        recv.await
+            .map_err(|_| LoginError::Exited)?
+            .context(errors::login::Client)
+    }
 
-        Ok(())
+    pub async fn journal(
+        &self,
+        key: JournalKey,
+    ) -> Result<Journal, RequestError> {
+        self.request(self.priority, |reply| Request::Journal(key, reply))
+            .await
+    }
+
+    pub async fn view(&self, key: ViewKey) -> Result<View, RequestError> {
+        self.request(self.priority, |reply| Request::View(key, reply))
+            .await
+    }
+
+    pub async fn user(&self, key: UserKey) -> Result<User, RequestError> {
+        self.request(self.priority, |reply| Request::User(key, reply))
+            .await
+    }
+
+    pub async fn gallery(
+        &self,
+        key: GalleryKey,
+    ) -> Result<Gallery, RequestError> {
+        self.request(self.priority, |reply| Request::Gallery(key, reply))
+            .await
+    }
+
+    pub async fn reply(
+        &self,
+        key: CommentReplyKey,
+        text: String,
+    ) -> Result<(), RequestError> {
+        self.action(|reply| Request::Reply(key, text, reply)).await
+    }
+
+    pub async fn fav(&self, key: FavKey) -> Result<View, RequestError> {
+        self.request(Priority::User, |reply| Request::Fav(key, reply))
+            .await
+    }
+
+    pub async fn unfav(&self, key: FavKey) -> Result<View, RequestError> {
+        self.request(Priority::User, |reply| Request::Unfav(key, reply))
+            .await
+    }
+
+    pub async fn others(&self) -> Result<Others, RequestError> {
+        self.request(self.priority, Request::Others).await
     }
 
     pub async fn submissions(
         &self,
         key: SubmissionsKey,
     ) -> Result<Submissions, RequestError> {
-        let (reply, recv) = oneshot::channel();
+        self.request(self.priority, |reply| Request::Submissions(key, reply))
+            .await
+    }
 
-        self.sender
-            .send(Message::Submissions(key, reply))
-            .map_err(|_| RequestError::Exited)?;
+    pub async fn clear_submissions(
+        &self,
+        keys: Vec<ViewKey>,
+    ) -> Result<(), RequestError> {
+        self.action(|reply| Request::ClearSubmissions(keys, reply))
+            .await
+    }
+}
 
-        Ok(self.recv(recv).await?)
+/// Drive `request` to completion and send its output to `reply`, unless the
+/// receiving end is dropped first, in which case `request` is dropped too.
+async fn respond<T, F>(mut reply: oneshot::Sender<T>, request: F)
+where
+    F: Future<Output = T>,
+{
+    tokio::select! {
+        result = request => {
+            reply.send(result).ok();
+        }
+        _ = reply.cancellation() => (),
     }
 }
 
@@ -104,15 +565,53 @@ pub struct Bridge {
 }
 
 impl Bridge {
-    pub fn spawn() -> Self {
+    /// Start a bridge talking to the real site, or to a [`Fake`] if the
+    /// environment asks for one (see [`Fake::from_env`]).
+    pub fn spawn(config: Config) -> Result<Self, ClientError> {
+        match Fake::from_env() {
+            Some(fake) => Ok(Self::with_backend(config, Box::new(fake))),
+            None => Self::with_config(config),
+        }
+    }
+
+    /// Start a bridge talking to the real site, or to `LABRAT_BASE_URL` if
+    /// it's set.
+    ///
+    /// A base URL that can't be used is ignored in favor of the real site.
+    pub fn with_config(config: Config) -> Result<Self, ClientError> {
+        let site = match backend::base_url() {
+            Some(base) => match Site::new(Some(base.clone()), None) {
+                Ok(site) => site,
+                Err(e) => {
+                    log::warn!("ignoring LABRAT_BASE_URL {}: {}", base, e);
+                    Site::new(None, None)?
+                }
+            },
+            None => Site::new(None, None)?,
+        };
+
+        Ok(Self::with_backend(config, Box::new(site)))
+    }
+
+    /// Start a bridge sending requests to `backend`, which should not be
+    /// logged in.
+    pub fn with_backend(config: Config, backend: Box<dyn Backend>) -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(16);
 
         let thread = thread::Builder::new()
             .name("labrat-bridge".into())
-            .spawn(move || Self::run(receiver))
+            .spawn({
+                let events = events.clone();
+                move || Self::run(config, backend, events, receiver)
+            })
             .unwrap();
 
-        let client = Client { sender };
+        let client = Client {
+            sender,
+            events,
+            priority: Priority::User,
+        };
 
         Self { thread, client }
     }
@@ -129,60 +628,245 @@ impl Bridge {
     }
 
     #[tokio::main(flavor = "current_thread")]
-    async fn run(mut receiver: mpsc::UnboundedReceiver<Message>) {
-        eprintln!("labrat running");
-        let mut client = labrat::client::Client::new().unwrap();
-
-        while let Some(message) = receiver.recv().await {
-            eprintln!("labrat got msg: {:?}", message);
-            match message {
-                Message::Stop => break,
-
-                Message::Replace(c, reply) => {
-                    client = c;
-                    reply.send(()).ok();
+    async fn run(
+        config: Config,
+        backend: Box<dyn Backend>,
+        events: broadcast::Sender<Event>,
+        mut receiver: mpsc::UnboundedReceiver<Message>,
+    ) {
+        log::debug!("bridge running");
+        let mut session = Arc::new(Session {
+            backend,
+            authenticated: false,
+        });
+        let (sessions, receiver_sessions) = watch::channel(session.clone());
+
+        let permits = Arc::new(Semaphore::new(config.max_concurrent.max(1)));
+        let mut queue = Queue::default();
+
+        let shared = Arc::new(Shared {
+            limiter: Limiter::new(config.requests_per_second, config.burst),
+            events,
+            sessions: receiver_sessions,
+            expired: AtomicBool::new(false),
+            config,
+        });
+
+        loop {
+            tokio::select! {
+                // Handle every waiting message before starting requests, so
+                // that priorities are respected.
+                biased;
+
+                message = receiver.recv() => {
+                    log::debug!("received {:?}", message);
+                    match message {
+                        None | Some(Message::Stop) => break,
+
+                        Some(Message::Replace(cookies, reply)) => {
+                            let authenticated = cookies.is_some();
+
+                            let backend =
+                                match session.backend.connect(cookies) {
+                                    Ok(b) => b,
+                                    Err(e) => {
+                                        reply.send(Err(e)).ok();
+                                        continue;
+                                    }
+                                };
+
+                            session = Arc::new(Session {
+                                backend,
+                                authenticated,
+                            });
+
+                            // Requests already queued keep using the client
+                            // they were sent to, unless it was logged out.
+                            if shared.expired.swap(false, Ordering::SeqCst) {
+                                queue.rebind(&session);
+                            }
+
+                            sessions.send(session.clone()).ok();
+                            reply.send(Ok(())).ok();
+                        }
+
+                        Some(Message::Request(priority, request)) => {
+                            queue.push(priority, session.clone(), request);
+                        }
+                    }
                 }
 
-                Message::Journal(key, reply) => {
-                    let result = client.journal(key).await;
-                    reply.send(result).ok();
+                permit = permits.clone().acquire_owned(),
+                    if !queue.is_empty() && !shared.is_expired() =>
+                {
+                    // The semaphore is never closed.
+                    let permit = permit.unwrap();
+                    let (session, request) = queue.pop().unwrap();
+                    let shared = shared.clone();
+
+                    tokio::spawn(async move {
+                        request.execute(session, &shared).await;
+                        drop(permit);
+                    });
                 }
+            }
+        }
+    }
+}
 
-                Message::View(key, reply) => {
-                    let result = client.view(key).await;
-                    reply.send(result).ok();
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                Message::Reply(key, text, reply) => {
-                    let result = client.reply(key, &text).await;
-                    reply.send(result).ok();
-                }
+    use reqwest::Url;
 
-                Message::Fav(key, reply) => {
-                    let result = client.fav(key).await;
-                    reply.send(result).ok();
-                }
+    use std::convert::TryFrom;
 
-                Message::Unfav(key, reply) => {
-                    let result = client.unfav(key).await;
-                    reply.send(result).ok();
-                }
+    fn config() -> Config {
+        Config {
+            requests_per_second: 0.,
+            backoff_base: Duration::from_millis(1),
+            backoff_max: Duration::from_millis(10),
+            retry_base: Duration::from_millis(1),
+            ..Config::default()
+        }
+    }
 
-                Message::Others(reply) => {
-                    let result = client.others().await;
-                    reply.send(result).ok();
-                }
+    fn fake(latency: Duration) -> Fake {
+        Fake::new("/nonexistent/labrat-gtk-fixtures", latency)
+    }
 
-                Message::Submissions(key, reply) => {
-                    let result = client.submissions(key).await;
-                    reply.send(result).ok();
-                }
+    fn view_key() -> ViewKey {
+        let url = Url::parse("https://www.furaffinity.net/view/1234/");
+        ViewKey::try_from(url.unwrap()).unwrap()
+    }
 
-                Message::ClearSubmissions(keys, reply) => {
-                    let result = client.clear_submissions(keys).await;
-                    reply.send(result).ok();
-                }
+    /// The backend error and attempt count of a failed request.
+    fn failure<T>(result: Result<T, RequestError>) -> (Error, u32) {
+        match result {
+            Err(RequestError::Request {
+                source, attempts, ..
+            }) => (source, attempts),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("request was meant to fail"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_are_retried() {
+        let fake = fake(Duration::default()).fail("view", Class::Transient, 2);
+        let bridge = Bridge::with_backend(config(), Box::new(fake));
+
+        let (error, attempts) = failure(bridge.client().view(view_key()).await);
+
+        // Out of injected failures, so the third attempt finds no fixture.
+        assert!(matches!(error, Error::MissingFixture { .. }));
+        assert_eq!(attempts, 3);
+
+        bridge.join();
+    }
+
+    #[tokio::test]
+    async fn reads_give_up_after_max_retries() {
+        let fake = fake(Duration::default()).fail("view", Class::Transient, 9);
+        let bridge = Bridge::with_backend(config(), Box::new(fake));
+
+        let (error, attempts) = failure(bridge.client().view(view_key()).await);
+
+        assert!(matches!(error, Error::Injected { .. }));
+        assert_eq!(attempts, config().max_retries + 1);
+
+        bridge.join();
+    }
+
+    #[tokio::test]
+    async fn mutations_are_not_retried() {
+        let fake = fake(Duration::default())
+            .fail("clear-submissions", Class::Transient, 1)
+            .fail("clear-submissions", Class::Unavailable, 1);
+        let bridge = Bridge::with_backend(config(), Box::new(fake));
+        let client = bridge.client();
+
+        for _ in 0..2 {
+            let result = client.clear_submissions(vec![]).await;
+            let (_, attempts) = failure(result);
+            assert_eq!(attempts, 1);
+        }
+
+        assert!(client.clear_submissions(vec![]).await.is_ok());
+
+        bridge.join();
+    }
+
+    #[tokio::test]
+    async fn refused_mutations_are_retried() {
+        let fake = fake(Duration::default()).fail(
+            "clear-submissions",
+            Class::RateLimited,
+            2,
+        );
+        let bridge = Bridge::with_backend(config(), Box::new(fake));
+        let mut events = bridge.client().events();
+
+        let result = bridge.client().clear_submissions(vec![]).await;
+        assert!(result.is_ok());
+
+        assert!(matches!(events.recv().await, Ok(Event::RateLimited { .. })));
+
+        bridge.join();
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_honored() {
+        // Longer than any delay `config` would back off for by itself.
+        let retry_after = Duration::from_millis(100);
+        let fake = fake(Duration::default()).throttle(
+            "clear-submissions",
+            1,
+            retry_after,
+        );
+        let bridge = Bridge::with_backend(config(), Box::new(fake));
+        let mut events = bridge.client().events();
+
+        let start = Instant::now();
+        let result = bridge.client().clear_submissions(vec![]).await;
+        assert!(result.is_ok());
+        assert!(start.elapsed() >= retry_after);
+
+        match events.recv().await {
+            Ok(Event::RateLimited { until }) => {
+                assert!(until >= start + retry_after)
             }
+            other => panic!("unexpected event: {:?}", other),
         }
+
+        bridge.join();
+    }
+
+    #[tokio::test]
+    async fn dropped_requests_are_cancelled() {
+        let latency = Duration::from_millis(100);
+        let fake = fake(latency).fail("view", Class::Fatal, 1);
+        let bridge = Bridge::with_backend(config(), Box::new(fake));
+        let client = bridge.client();
+
+        let view = client.view(view_key());
+        let timeout = tokio::time::timeout(latency / 4, view).await;
+        assert!(timeout.is_err());
+
+        // Had the first request gone on, it would have used up the failure.
+        tokio::time::sleep(latency * 2).await;
+
+        let (error, attempts) = failure(client.view(view_key()).await);
+        assert!(matches!(
+            error,
+            Error::Injected {
+                class: Class::Fatal,
+                ..
+            }
+        ));
+        assert_eq!(attempts, 1);
+
+        bridge.join();
     }
 }
@@ -0,0 +1,252 @@
+//! What the bridge sends requests to: the real site (or a stand-in server)
+//! through labrat, or a [`Fake`] serving fixture files.
+
+mod fake;
+
+pub use self::fake::Fake;
+
+use labrat::client::{ClientError, Response};
+use labrat::keys::{
+    CommentReplyKey, FavKey, GalleryKey, JournalKey, SubmissionsKey, UserKey,
+    ViewKey,
+};
+use labrat::resources::gallery::Gallery;
+use labrat::resources::journal::Journal;
+use labrat::resources::msg::others::Others;
+use labrat::resources::msg::submissions::Submissions;
+use labrat::resources::user::User;
+use labrat::resources::view::View;
+
+use reqwest::header::{HeaderValue, RETRY_AFTER};
+use reqwest::Url;
+
+use snafu::{ResultExt, Snafu};
+
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
+
+use super::backoff::{self, Class};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+type Reply<'a, T> = BoxFuture<'a, Result<Response<T>, Error>>;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The request to the site failed.
+    #[snafu(display("{}", source))]
+    Labrat {
+        source: labrat::client::RequestError<Infallible>,
+
+        /// How long the server asked us to wait before trying again.
+        retry_after: Option<Duration>,
+    },
+
+    /// Failure injected by a fake backend, handled as if it were `class`.
+    #[snafu(display("injected {:?} failure", class))]
+    Injected {
+        class: Class,
+        retry_after: Option<Duration>,
+    },
+
+    /// A fake backend has no fixture for the request.
+    #[snafu(display("no fixture for {}", request))]
+    MissingFixture { request: String },
+
+    /// A fake backend couldn't read a fixture.
+    #[snafu(display("unable to read fixture {}: {}", path, source))]
+    ReadFixture {
+        path: String,
+        source: std::io::Error,
+    },
+
+    /// A fake backend couldn't parse a fixture.
+    #[snafu(display("unable to parse fixture {}: {}", path, message))]
+    ParseFixture { path: String, message: String },
+}
+
+impl Error {
+    /// How long the server asked us to wait before trying again, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Labrat { retry_after, .. } => *retry_after,
+            Error::Injected { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// A site the bridge can make requests to.
+///
+/// Methods mirror `labrat::client::Client`, boxing their futures so the
+/// bridge can hold any backend behind a `dyn Backend`.
+pub trait Backend: fmt::Debug + Send + Sync + 'static {
+    /// A backend for the same site using `cookies`, or logged out if there
+    /// aren't any.
+    fn connect(
+        &self,
+        cookies: Option<HeaderValue>,
+    ) -> Result<Box<dyn Backend>, ClientError>;
+
+    fn journal(&self, key: JournalKey) -> Reply<'_, Journal>;
+    fn view(&self, key: ViewKey) -> Reply<'_, View>;
+    fn user(&self, key: UserKey) -> Reply<'_, User>;
+    fn gallery(&self, key: GalleryKey) -> Reply<'_, Gallery>;
+    fn reply(
+        &self,
+        key: CommentReplyKey,
+        text: String,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+    fn fav(&self, key: FavKey) -> Reply<'_, View>;
+    fn unfav(&self, key: FavKey) -> Reply<'_, View>;
+    fn others(&self) -> Reply<'_, Others>;
+    fn submissions(&self, key: SubmissionsKey) -> Reply<'_, Submissions>;
+    fn clear_submissions(
+        &self,
+        keys: Vec<ViewKey>,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+}
+
+/// Base URL to use instead of the real site, from `LABRAT_BASE_URL`.
+///
+/// Meant for pointing the app at a stand-in server, like the `standin`
+/// example.
+pub fn base_url() -> Option<Url> {
+    let base = std::env::var("LABRAT_BASE_URL").ok()?;
+
+    match Url::parse(&base) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            log::warn!("ignoring LABRAT_BASE_URL {:?}: {}", base, e);
+            None
+        }
+    }
+}
+
+/// The site, reached through labrat.
+#[derive(Debug)]
+pub struct Site {
+    base: Option<Url>,
+    client: labrat::client::Client,
+
+    /// Asks for `Retry-After` when the site throttles a request.
+    probe: reqwest::Client,
+}
+
+impl Site {
+    /// Connect to `base`, or the real site if there isn't one.
+    pub fn new(
+        base: Option<Url>,
+        cookies: Option<HeaderValue>,
+    ) -> Result<Self, ClientError> {
+        let client = match (base.clone(), cookies) {
+            (Some(b), c) => labrat::client::Client::with_base_url(b, c)?,
+            (None, Some(c)) => labrat::client::Client::with_cookies(c)?,
+            (None, None) => labrat::client::Client::new()?,
+        };
+
+        Ok(Self {
+            base,
+            client,
+            probe: reqwest::Client::new(),
+        })
+    }
+
+    /// Wait for a request made through labrat, finding out how long to back
+    /// off for if the site throttled it.
+    async fn send<T, F>(&self, request: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, labrat::client::RequestError<Infallible>>>,
+    {
+        let source = match request.await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+
+        let retry_after = match backoff::throttled_url(&source) {
+            Some(url) => self.retry_after(url).await,
+            None => None,
+        };
+
+        Err(source).context(Labrat { retry_after })
+    }
+
+    /// Ask the site again, without a body, for the `Retry-After` of a
+    /// throttled request.
+    ///
+    /// labrat drops the response headers of failed requests, so they can't
+    /// be read from the original response.
+    async fn retry_after(&self, url: Url) -> Option<Duration> {
+        let response = match self.probe.head(url).send().await {
+            Ok(r) => r,
+            Err(e) => {
+                log::debug!("unable to ask for Retry-After: {}", e);
+                return None;
+            }
+        };
+
+        let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+        backoff::retry_after(value, SystemTime::now())
+    }
+}
+
+impl Backend for Site {
+    fn connect(
+        &self,
+        cookies: Option<HeaderValue>,
+    ) -> Result<Box<dyn Backend>, ClientError> {
+        Ok(Box::new(Site::new(self.base.clone(), cookies)?))
+    }
+
+    fn journal(&self, key: JournalKey) -> Reply<'_, Journal> {
+        Box::pin(async move { self.send(self.client.journal(key)).await })
+    }
+
+    fn view(&self, key: ViewKey) -> Reply<'_, View> {
+        Box::pin(async move { self.send(self.client.view(key)).await })
+    }
+
+    fn user(&self, key: UserKey) -> Reply<'_, User> {
+        Box::pin(async move { self.send(self.client.user(key)).await })
+    }
+
+    fn gallery(&self, key: GalleryKey) -> Reply<'_, Gallery> {
+        Box::pin(async move { self.send(self.client.gallery(key)).await })
+    }
+
+    fn reply(
+        &self,
+        key: CommentReplyKey,
+        text: String,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move { self.send(self.client.reply(key, &text)).await })
+    }
+
+    fn fav(&self, key: FavKey) -> Reply<'_, View> {
+        Box::pin(async move { self.send(self.client.fav(key)).await })
+    }
+
+    fn unfav(&self, key: FavKey) -> Reply<'_, View> {
+        Box::pin(async move { self.send(self.client.unfav(key)).await })
+    }
+
+    fn others(&self) -> Reply<'_, Others> {
+        Box::pin(async move { self.send(self.client.others()).await })
+    }
+
+    fn submissions(&self, key: SubmissionsKey) -> Reply<'_, Submissions> {
+        Box::pin(async move { self.send(self.client.submissions(key)).await })
+    }
+
+    fn clear_submissions(
+        &self,
+        keys: Vec<ViewKey>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(
+            async move { self.send(self.client.clear_submissions(keys)).await },
+        )
+    }
+}
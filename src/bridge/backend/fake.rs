@@ -0,0 +1,385 @@
+//! Stand-in for the site that serves saved pages, so the UI can run without
+//! a network connection and with the same data every time.
+//!
+//! Fixtures are pages saved from the site, in a directory laid out as:
+//!
+//!  - `view/<id>.html` answers requests for the submission with that id.
+//!  - `submissions/<page>.html` answers the `<page>`th distinct submissions
+//!    key requested, counting from one.
+//!  - `<kind>.html` answers every other request of that kind.
+//!
+//! Kinds are `journal`, `view`, `user`, `gallery`, `others` and
+//! `submissions`. Favoriting looks for `fav.html` or `unfav.html` first,
+//! then falls back to `view.html`. Replying and clearing submissions
+//! succeed unless a failure is injected for `reply` or `clear-submissions`.
+
+use labrat::client::{ClientError, Response};
+use labrat::keys::{
+    CommentReplyKey, FavKey, GalleryKey, JournalKey, SubmissionsKey, UserKey,
+    ViewKey,
+};
+use labrat::resources::gallery::Gallery;
+use labrat::resources::journal::Journal;
+use labrat::resources::msg::others::Others;
+use labrat::resources::msg::submissions::Submissions;
+use labrat::resources::user::User;
+use labrat::resources::view::View;
+use labrat::resources::FromHtml;
+
+use reqwest::header::HeaderValue;
+
+use snafu::{OptionExt, ResultExt};
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{Backend, BoxFuture, Error, Reply};
+use super::{Injected, MissingFixture, ParseFixture, ReadFixture};
+use crate::bridge::backoff::Class;
+
+#[derive(Debug)]
+struct Failure {
+    kind: String,
+    class: Class,
+    retry_after: Option<Duration>,
+    remaining: u32,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+    latency: Duration,
+    failures: Mutex<Vec<Failure>>,
+
+    /// Submissions keys in the order they were first requested.
+    pages: Mutex<Vec<SubmissionsKey>>,
+}
+
+/// Backend answering from fixture files, see the module documentation.
+///
+/// Clones, and backends made with `connect`, share fixtures and injected
+/// failures.
+#[derive(Debug, Clone)]
+pub struct Fake(Arc<Inner>);
+
+impl Fake {
+    /// Serve fixtures from `dir`, delaying every response by `latency`.
+    pub fn new<P: Into<PathBuf>>(dir: P, latency: Duration) -> Self {
+        Self(Arc::new(Inner {
+            dir: dir.into(),
+            latency,
+            failures: Mutex::default(),
+            pages: Mutex::default(),
+        }))
+    }
+
+    /// Fail the next `times` requests of `kind` as if they failed for
+    /// `class`.
+    pub fn fail(self, kind: &str, class: Class, times: u32) -> Self {
+        self.0.failures.lock().unwrap().push(Failure {
+            kind: kind.to_owned(),
+            class,
+            retry_after: None,
+            remaining: times,
+        });
+
+        self
+    }
+
+    /// Rate limit the next `times` requests of `kind`, asking for a wait of
+    /// `retry_after` before trying again.
+    pub fn throttle(
+        self,
+        kind: &str,
+        times: u32,
+        retry_after: Duration,
+    ) -> Self {
+        self.0.failures.lock().unwrap().push(Failure {
+            kind: kind.to_owned(),
+            class: Class::RateLimited,
+            retry_after: Some(retry_after),
+            remaining: times,
+        });
+
+        self
+    }
+
+    /// Build a fake from the environment, if `LABRAT_FAKE` names a fixture
+    /// directory.
+    ///
+    /// `LABRAT_FAKE_LATENCY` delays every response by that many
+    /// milliseconds, and `LABRAT_FAKE_FAIL` injects failures as a comma
+    /// separated list of `kind:class:times`, where `class` is one of
+    /// `rate-limited`, `unavailable`, `transient` or `fatal`.
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var_os("LABRAT_FAKE")?;
+
+        let latency = std::env::var("LABRAT_FAKE_LATENCY")
+            .ok()
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(0);
+
+        let mut fake = Self::new(dir, Duration::from_millis(latency));
+
+        let failures = std::env::var("LABRAT_FAKE_FAIL").unwrap_or_default();
+
+        for failure in failures.split(',').filter(|f| !f.is_empty()) {
+            let mut parts = failure.splitn(3, ':');
+
+            let kind = parts.next().unwrap_or_default();
+            let class = match parts.next() {
+                Some("rate-limited") => Class::RateLimited,
+                Some("unavailable") => Class::Unavailable,
+                Some("transient") => Class::Transient,
+                Some("fatal") => Class::Fatal,
+                _ => {
+                    log::warn!("ignoring fake failure {:?}", failure);
+                    continue;
+                }
+            };
+            let times = parts.next().and_then(|t| t.parse().ok()).unwrap_or(1);
+
+            fake = fake.fail(kind, class, times);
+        }
+
+        Some(fake)
+    }
+
+    /// Wait out the latency, then fail if a failure was injected for `kind`.
+    async fn respond(&self, kind: &str) -> Result<(), Error> {
+        if self.0.latency > Duration::default() {
+            tokio::time::sleep(self.0.latency).await;
+        }
+
+        let mut failures = self.0.failures.lock().unwrap();
+
+        let failure = failures
+            .iter_mut()
+            .find(|f| f.kind == kind && f.remaining > 0);
+
+        match failure {
+            Some(f) => {
+                f.remaining -= 1;
+                Injected {
+                    class: f.class,
+                    retry_after: f.retry_after,
+                }
+                .fail()
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Page number of `key`, counting from one in the order keys are first
+    /// requested.
+    fn page_number(&self, key: SubmissionsKey) -> String {
+        let mut pages = self.0.pages.lock().unwrap();
+
+        let index = match pages.iter().position(|p| *p == key) {
+            Some(i) => i,
+            None => {
+                pages.push(key);
+                pages.len() - 1
+            }
+        };
+
+        (index + 1).to_string()
+    }
+
+    /// Path of the first fixture that exists for any of `kinds`.
+    fn fixture(&self, kinds: &[&str], name: Option<&str>) -> Option<PathBuf> {
+        for kind in kinds {
+            if let Some(name) = name {
+                let path = self.0.dir.join(kind).join(format!("{}.html", name));
+
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+
+            let path = self.0.dir.join(format!("{}.html", kind));
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+
+        None
+    }
+
+    async fn page<T>(
+        &self,
+        kinds: &[&str],
+        name: Option<String>,
+    ) -> Result<Response<T>, Error>
+    where
+        T: FromHtml + Send,
+    {
+        self.respond(kinds[0]).await?;
+
+        let path = self.fixture(kinds, name.as_deref()).with_context(|| {
+            let request = match name {
+                Some(s) => format!("{}/{}", kinds[0], s),
+                None => kinds[0].to_owned(),
+            };
+
+            MissingFixture { request }
+        })?;
+
+        let display = path.display().to_string();
+
+        let html = std::fs::read_to_string(&path).context(ReadFixture {
+            path: display.clone(),
+        })?;
+
+        match Response::from_html(&html) {
+            Ok(response) => Ok(response),
+            Err(e) => ParseFixture {
+                path: display,
+                message: e.to_string(),
+            }
+            .fail(),
+        }
+    }
+}
+
+impl Backend for Fake {
+    fn connect(
+        &self,
+        _cookies: Option<HeaderValue>,
+    ) -> Result<Box<dyn Backend>, ClientError> {
+        // Fixtures decide whether pages look logged in, not cookies.
+        Ok(Box::new(self.clone()))
+    }
+
+    fn journal(&self, _key: JournalKey) -> Reply<'_, Journal> {
+        Box::pin(self.page(&["journal"], None))
+    }
+
+    fn view(&self, key: ViewKey) -> Reply<'_, View> {
+        Box::pin(self.page(&["view"], Some(key.view_id.to_string())))
+    }
+
+    fn user(&self, _key: UserKey) -> Reply<'_, User> {
+        Box::pin(self.page(&["user"], None))
+    }
+
+    fn gallery(&self, _key: GalleryKey) -> Reply<'_, Gallery> {
+        Box::pin(self.page(&["gallery"], None))
+    }
+
+    fn reply(
+        &self,
+        _key: CommentReplyKey,
+        _text: String,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(self.respond("reply"))
+    }
+
+    fn fav(&self, _key: FavKey) -> Reply<'_, View> {
+        Box::pin(self.page(&["fav", "view"], None))
+    }
+
+    fn unfav(&self, _key: FavKey) -> Reply<'_, View> {
+        Box::pin(self.page(&["unfav", "view"], None))
+    }
+
+    fn others(&self) -> Reply<'_, Others> {
+        Box::pin(self.page(&["others"], None))
+    }
+
+    fn submissions(&self, key: SubmissionsKey) -> Reply<'_, Submissions> {
+        let page = self.page_number(key);
+        Box::pin(self.page(&["submissions"], Some(page)))
+    }
+
+    fn clear_submissions(
+        &self,
+        _keys: Vec<ViewKey>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(self.respond("clear-submissions"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    /// An empty fixture directory, removed when dropped.
+    fn fixtures() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("submissions")).unwrap();
+        dir
+    }
+
+    /// Path of the fixture `result` tried to parse.
+    fn parsed<T>(result: Result<T, Error>) -> String {
+        match result {
+            Err(Error::ParseFixture { path, .. }) => path,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("fixture wasn't meant to parse"),
+        }
+    }
+
+    #[tokio::test]
+    async fn submissions_are_numbered_by_page() {
+        let fixtures = fixtures();
+        let dir = fixtures.path();
+
+        // Not real pages, so which one was picked shows up in the error.
+        for page in &["1", "2"] {
+            let path = dir.join("submissions").join(format!("{}.html", page));
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::write(dir.join("submissions.html"), "").unwrap();
+
+        let fake = Fake::new(dir, Duration::default());
+
+        let first = parsed(fake.submissions(SubmissionsKey::oldest()).await);
+        let second = parsed(fake.submissions(SubmissionsKey::newest()).await);
+        let again = parsed(fake.submissions(SubmissionsKey::oldest()).await);
+
+        assert!(first.ends_with("1.html"), "{}", first);
+        assert!(second.ends_with("2.html"), "{}", second);
+        assert_eq!(again, first);
+    }
+
+    #[tokio::test]
+    async fn missing_fixture_names_the_request() {
+        let fixtures = fixtures();
+        let dir = fixtures.path();
+        let fake = Fake::new(dir, Duration::default());
+
+        match fake.submissions(SubmissionsKey::oldest()).await {
+            Err(Error::MissingFixture { request }) => {
+                assert_eq!(request, "submissions/1")
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn failures_run_out() {
+        let fake = Fake::new("/nonexistent", Duration::default()).fail(
+            "reply",
+            Class::Transient,
+            2,
+        );
+
+        for _ in 0..2 {
+            let result = fake.respond("reply").await;
+            assert!(matches!(
+                result,
+                Err(Error::Injected {
+                    class: Class::Transient,
+                    ..
+                })
+            ));
+        }
+
+        assert!(fake.respond("reply").await.is_ok());
+        assert!(fake.respond("view").await.is_ok());
+    }
+}
@@ -0,0 +1,185 @@
+use rand::Rng;
+
+use reqwest::{StatusCode, Url};
+
+use std::convert::Infallible;
+use std::time::{Duration, SystemTime};
+
+use super::Error;
+
+/// How a failed request should be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// The server refused the request and asked us to slow down.
+    RateLimited,
+
+    /// The server is overloaded. Other requests should slow down too, but
+    /// unlike `RateLimited` the request may have been handled.
+    Unavailable,
+
+    /// Something that might work if tried again, like a timeout.
+    Transient,
+
+    /// Anything else; the error is passed on to the caller.
+    Fatal,
+}
+
+fn reqwest_error(
+    error: &(dyn std::error::Error + 'static),
+) -> Option<&reqwest::Error> {
+    let mut source = Some(error);
+
+    while let Some(current) = source {
+        if let Some(found) = current.downcast_ref::<reqwest::Error>() {
+            return Some(found);
+        }
+
+        source = current.source();
+    }
+
+    None
+}
+
+pub(super) fn classify(error: &Error) -> Class {
+    let error = match error {
+        Error::Labrat { source, .. } => source,
+        Error::Injected { class } => return *class,
+        _ => return Class::Fatal,
+    };
+
+    let error = match reqwest_error(error) {
+        Some(e) => e,
+        None => return Class::Fatal,
+    };
+
+    match error.status() {
+        Some(StatusCode::TOO_MANY_REQUESTS) => Class::RateLimited,
+        Some(StatusCode::SERVICE_UNAVAILABLE) => Class::Unavailable,
+        Some(s) if s.is_server_error() => Class::Transient,
+        Some(_) => Class::Fatal,
+        None if error.is_timeout() || error.is_connect() => Class::Transient,
+        None => Class::Fatal,
+    }
+}
+
+/// Where a request went, if the server refused it and might say how long to
+/// back off for.
+pub(super) fn throttled_url(
+    error: &labrat::client::RequestError<Infallible>,
+) -> Option<Url> {
+    let error = reqwest_error(error)?;
+
+    match error.status()? {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+            error.url().cloned()
+        }
+        _ => None,
+    }
+}
+
+/// How long a `Retry-After` header asks us to wait, counting from `now`.
+///
+/// The header is either a number of seconds or an HTTP date.
+pub(super) fn retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+
+    // A date in the past means the wait is already over.
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Exponential backoff with jitter for the given (one-based) attempt.
+pub(super) fn delay(attempt: u32, base: Duration, max: Duration) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    let ceiling = base.checked_mul(factor).unwrap_or(max).min(max);
+
+    // Wait somewhere between half and all of the ceiling, so clients that
+    // were throttled together don't all retry together.
+    let half = ceiling / 2;
+    let jitter = rand::thread_rng().gen_range(0.0..=1.0);
+
+    half + half.mul_f64(jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_max() {
+        let base = Duration::from_secs(2);
+        let max = Duration::from_secs(60);
+
+        for attempt in 1..=10 {
+            let ceiling = (base * 2u32.pow(attempt - 1)).min(max);
+            let delay = delay(attempt, base, max);
+
+            assert!(delay >= ceiling / 2, "{:?} < {:?}", delay, ceiling / 2);
+            assert!(delay <= ceiling, "{:?} > {:?}", delay, ceiling);
+        }
+    }
+
+    #[test]
+    fn delay_saturates() {
+        let max = Duration::from_secs(300);
+        let delay = delay(u32::MAX, Duration::from_secs(1), max);
+
+        assert!(delay >= max / 2);
+        assert!(delay <= max);
+    }
+
+    #[test]
+    fn delay_treats_zero_as_first_attempt() {
+        let base = Duration::from_secs(4);
+        let delay = delay(0, base, Duration::from_secs(60));
+
+        assert!(delay >= base / 2);
+        assert!(delay <= base);
+    }
+
+    #[test]
+    fn classify_injected() {
+        let error = Error::Injected {
+            class: Class::Transient,
+            retry_after: None,
+        };
+        assert_eq!(classify(&error), Class::Transient);
+
+        let error = Error::MissingFixture {
+            request: "view".into(),
+        };
+        assert_eq!(classify(&error), Class::Fatal);
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        let now = SystemTime::now();
+        let delay = retry_after(" 120 ", now);
+        assert_eq!(delay, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn retry_after_date() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT");
+        let delay = retry_after("Wed, 21 Oct 2015 07:30:00 GMT", now.unwrap());
+        assert_eq!(delay, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn retry_after_past_date() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT");
+        let delay = retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now.unwrap());
+        assert_eq!(delay, Some(Duration::default()));
+    }
+
+    #[test]
+    fn retry_after_garbage() {
+        assert_eq!(retry_after("soon", SystemTime::now()), None);
+        assert_eq!(retry_after("-5", SystemTime::now()), None);
+    }
+}
@@ -19,13 +19,17 @@ pub(super) mod login {
 pub(super) mod request {
     use snafu::{Backtrace, Snafu};
 
-    use std::convert::Infallible;
-
     #[derive(Debug, Snafu)]
     #[snafu(visibility = "pub(crate)")]
     pub enum RequestError {
+        #[snafu(display(
+            "request failed after {} attempt(s): {}",
+            attempts,
+            source
+        ))]
         Request {
-            source: labrat::client::RequestError<Infallible>,
+            source: crate::bridge::backend::Error,
+            attempts: u32,
             backtrace: Backtrace,
         },
         Exited,
@@ -0,0 +1,190 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+
+    paused_until: Option<Instant>,
+    limited: bool,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.burst);
+        self.updated = now;
+    }
+
+    /// Take a token, or return how long to wait before trying again.
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.paused_until {
+            if until > now {
+                return Some(until - now);
+            }
+
+            self.paused_until = None;
+        }
+
+        if self.rate <= 0. {
+            return None;
+        }
+
+        self.refill(now);
+
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1. - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// Token bucket shared by every request the bridge makes.
+///
+/// A `rate` of zero disables the bucket, but pauses requested by the server
+/// are still honored.
+#[derive(Debug)]
+pub(super) struct Limiter(Mutex<Bucket>);
+
+impl Limiter {
+    pub fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+
+        Self(Mutex::new(Bucket {
+            rate,
+            burst,
+            tokens: burst,
+            updated: Instant::now(),
+            paused_until: None,
+            limited: false,
+        }))
+    }
+
+    pub async fn acquire(&self) {
+        loop {
+            let wait = self.0.lock().unwrap().take(Instant::now());
+
+            match wait {
+                None => return,
+                Some(duration) => tokio::time::sleep(duration).await,
+            }
+        }
+    }
+
+    /// Hold back every request for at least `delay`.
+    pub fn pause(&self, delay: Duration) -> Instant {
+        let mut bucket = self.0.lock().unwrap();
+
+        let until = Instant::now() + delay;
+        let until = match bucket.paused_until {
+            Some(existing) if existing > until => existing,
+            _ => until,
+        };
+
+        bucket.paused_until = Some(until);
+        bucket.limited = true;
+
+        until
+    }
+
+    /// Note that a request went through, returning `true` if requests were
+    /// being held back by a pause until now.
+    pub fn resume(&self) -> bool {
+        let mut bucket = self.0.lock().unwrap();
+        std::mem::replace(&mut bucket.limited, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(rate: f64, burst: u32, now: Instant) -> Bucket {
+        let limiter = Limiter::new(rate, burst);
+        let mut bucket = limiter.0.into_inner().unwrap();
+        bucket.updated = now;
+        bucket
+    }
+
+    #[test]
+    fn take_allows_burst() {
+        let now = Instant::now();
+        let mut bucket = bucket(2., 3, now);
+
+        for _ in 0..3 {
+            assert_eq!(bucket.take(now), None);
+        }
+
+        let wait = bucket.take(now).unwrap();
+        assert_eq!(wait, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn take_refills_over_time() {
+        let now = Instant::now();
+        let mut bucket = bucket(2., 1, now);
+
+        assert_eq!(bucket.take(now), None);
+        assert!(bucket.take(now).is_some());
+
+        let later = now + Duration::from_millis(500);
+        assert_eq!(bucket.take(later), None);
+    }
+
+    #[test]
+    fn refill_stops_at_burst() {
+        let now = Instant::now();
+        let mut bucket = bucket(10., 2, now);
+
+        bucket.refill(now + Duration::from_secs(60));
+        assert!((bucket.tokens - 2.).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn zero_rate_is_unlimited() {
+        let now = Instant::now();
+        let mut bucket = bucket(0., 1, now);
+
+        for _ in 0..100 {
+            assert_eq!(bucket.take(now), None);
+        }
+    }
+
+    #[test]
+    fn pause_holds_back_requests() {
+        let limiter = Limiter::new(0., 1);
+        let until = limiter.pause(Duration::from_secs(30));
+
+        let mut bucket = limiter.0.lock().unwrap();
+        let now = Instant::now();
+
+        assert!(bucket.take(now).is_some());
+        assert_eq!(bucket.take(until), None);
+        assert_eq!(bucket.paused_until, None);
+    }
+
+    #[test]
+    fn shorter_pause_keeps_longer() {
+        let limiter = Limiter::new(1., 1);
+        let long = limiter.pause(Duration::from_secs(60));
+        let short = limiter.pause(Duration::from_secs(1));
+
+        assert_eq!(long, short);
+    }
+
+    #[test]
+    fn resume_reports_pause_once() {
+        let limiter = Limiter::new(1., 1);
+        assert!(!limiter.resume());
+
+        limiter.pause(Duration::from_secs(1));
+        assert!(limiter.resume());
+        assert!(!limiter.resume());
+    }
+}
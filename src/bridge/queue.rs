@@ -0,0 +1,48 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use super::{Priority, Request, Session};
+
+type Entry = (Arc<Session>, Request);
+
+/// Requests waiting for a free slot in the bridge, split by priority.
+#[derive(Debug, Default)]
+pub(super) struct Queue {
+    user: VecDeque<Entry>,
+    background: VecDeque<Entry>,
+}
+
+impl Queue {
+    pub fn push(
+        &mut self,
+        priority: Priority,
+        session: Arc<Session>,
+        request: Request,
+    ) {
+        let lane = match priority {
+            Priority::User => &mut self.user,
+            Priority::Background => &mut self.background,
+        };
+
+        lane.push_back((session, request));
+    }
+
+    pub fn pop(&mut self) -> Option<Entry> {
+        self.user
+            .pop_front()
+            .or_else(|| self.background.pop_front())
+    }
+
+    /// Send every queued request with `session` instead.
+    pub fn rebind(&mut self, session: &Arc<Session>) {
+        let entries = self.user.iter_mut().chain(self.background.iter_mut());
+
+        for entry in entries {
+            entry.0 = session.clone();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.user.is_empty() && self.background.is_empty()
+    }
+}
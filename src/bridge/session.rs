@@ -0,0 +1,31 @@
+use labrat::client::Response;
+
+use super::backend::Backend;
+
+#[derive(Debug)]
+pub(super) struct Session {
+    pub backend: Box<dyn Backend>,
+
+    /// Whether `backend` was created with cookies, and so should only ever
+    /// see logged in pages.
+    pub authenticated: bool,
+}
+
+/// Successful results the bridge can check for signs of being logged out.
+pub(super) trait Page {
+    fn logged_in(&self) -> bool;
+}
+
+impl<T> Page for Response<T> {
+    fn logged_in(&self) -> bool {
+        // Logged out pages don't show the current user in the header.
+        self.header.me().is_some()
+    }
+}
+
+impl Page for () {
+    /// Actions don't return a page, so assume they worked.
+    fn logged_in(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,464 @@
+//! Reading FurAffinity cookies out of local browser profiles.
+
+use directories::BaseDirs;
+
+use rusqlite::Connection;
+
+pub use self::error::Error;
+
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+mod error {
+    use super::*;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        /// Unable to find the home directory.
+        HomeNotFound { backtrace: Backtrace },
+
+        /// Unable to copy the browser's cookie database.
+        CopyDatabase {
+            backtrace: Backtrace,
+            source: std::io::Error,
+        },
+
+        /// Unable to read the browser's cookie database.
+        Database {
+            backtrace: Backtrace,
+            source: rusqlite::Error,
+        },
+
+        /// The browser encrypts its cookies, so they can't be read.
+        #[snafu(display("{} encrypts its cookies", browser))]
+        Encrypted {
+            browser: super::Browser,
+            backtrace: Backtrace,
+        },
+    }
+}
+
+/// Domain the session cookies are set for.
+const DOMAIN: &str = "furaffinity.net";
+
+/// `LIKE` pattern for subdomains of [`DOMAIN`], and the domain itself as
+/// browsers store it for cookies shared with subdomains.
+const SUBDOMAINS: &str = "%.furaffinity.net";
+
+/// Names of the cookies making up a session.
+const NAMES: &[&str] = &["a", "b"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Firefox,
+    Chromium,
+    Chrome,
+    Brave,
+}
+
+impl fmt::Display for Browser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Browser::Firefox => "Firefox",
+            Browser::Chromium => "Chromium",
+            Browser::Chrome => "Google Chrome",
+            Browser::Brave => "Brave",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub browser: Browser,
+    pub name: String,
+    cookies: PathBuf,
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.browser, self.name)
+    }
+}
+
+/// A cookie for the site, as stored by a browser.
+#[derive(Debug)]
+struct Row {
+    name: String,
+    value: String,
+    encrypted: bool,
+
+    /// Whether the cookie belongs to ordinary browsing, rather than a
+    /// container or private window.
+    default: bool,
+
+    /// When the cookie was last used, in the browser's own units.
+    accessed: i64,
+}
+
+/// Build a cookie header out of `rows`, picking one cookie for each of
+/// `NAMES`.
+///
+/// Browsers can store a cookie more than once, so cookies from ordinary
+/// browsing are preferred, then the most recently used.
+fn assemble(browser: Browser, rows: &[Row]) -> Result<Option<String>, Error> {
+    let mut found = Vec::with_capacity(NAMES.len());
+
+    for name in NAMES {
+        let best = rows
+            .iter()
+            .filter(|r| r.name == *name)
+            .max_by_key(|r| (r.default, r.accessed));
+
+        let row = match best {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
+        if row.value.is_empty() && row.encrypted {
+            return error::Encrypted { browser }.fail();
+        }
+
+        found.push(format!("{}={}", row.name, row.value));
+    }
+
+    Ok(Some(found.join("; ")))
+}
+
+impl Profile {
+    /// Build the cookie header for `bridge::Client::login` from this
+    /// profile, if it has a FurAffinity session.
+    pub fn cookie(&self) -> Result<Option<String>, Error> {
+        let (_dir, copy) = self.copy_database()?;
+        let db = Connection::open(copy).context(error::Database)?;
+
+        let query = match self.browser {
+            Browser::Firefox => {
+                "SELECT name, value, x'', originAttributes = '', \
+                 lastAccessed FROM moz_cookies \
+                 WHERE host = ?1 OR host LIKE ?2"
+            }
+            _ => {
+                "SELECT name, value, encrypted_value, 1, last_access_utc \
+                 FROM cookies WHERE host_key = ?1 OR host_key LIKE ?2"
+            }
+        };
+
+        let mut statement = db.prepare(query).context(error::Database)?;
+        let rows = statement
+            .query_map(&[DOMAIN, SUBDOMAINS], |row| {
+                Ok(Row {
+                    name: row.get(0)?,
+                    value: row.get(1)?,
+                    encrypted: !row.get::<_, Vec<u8>>(2)?.is_empty(),
+                    default: row.get(3)?,
+                    accessed: row.get(4)?,
+                })
+            })
+            .context(error::Database)?
+            .collect::<Result<Vec<_>, _>>()
+            .context(error::Database)?;
+
+        assemble(self.browser, &rows)
+    }
+
+    /// Copy the cookie database, and the write-ahead log next to it if
+    /// there is one, into a directory removed when it's dropped.
+    ///
+    /// Browsers keep their databases locked while running, so they can't be
+    /// opened in place. Recent changes, like logging in a moment ago, can
+    /// still be in the log instead of the database itself.
+    fn copy_database(&self) -> Result<(TempDir, PathBuf), Error> {
+        let dir = TempDir::new().context(error::CopyDatabase)?;
+        let copy = dir.path().join("cookies");
+
+        std::fs::copy(&self.cookies, &copy).context(error::CopyDatabase)?;
+
+        let mut wal = self.cookies.clone().into_os_string();
+        wal.push("-wal");
+
+        match std::fs::copy(wal, dir.path().join("cookies-wal")) {
+            Ok(_) => (),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e).context(error::CopyDatabase),
+        }
+
+        Ok((dir, copy))
+    }
+}
+
+fn firefox(root: &Path, profiles: &mut Vec<Profile>) {
+    let ini = match std::fs::read_to_string(root.join("profiles.ini")) {
+        Ok(i) => i,
+        Err(_) => return,
+    };
+
+    let mut name = None;
+    let mut path = None;
+    let mut relative = true;
+
+    // Sections end at the next header, so push one past the last line too.
+    for line in ini.lines().chain(std::iter::once("[]")) {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            if let (Some(n), Some(p)) = (name.take(), path.take()) {
+                let dir = if relative {
+                    root.join(p)
+                } else {
+                    PathBuf::from(p)
+                };
+                let cookies = dir.join("cookies.sqlite");
+
+                if cookies.is_file() {
+                    profiles.push(Profile {
+                        browser: Browser::Firefox,
+                        name: n,
+                        cookies,
+                    });
+                }
+            }
+
+            relative = true;
+            continue;
+        }
+
+        let mut parts = line.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("Name"), Some(v)) => name = Some(v.to_owned()),
+            (Some("Path"), Some(v)) => path = Some(v.to_owned()),
+            (Some("IsRelative"), Some(v)) => relative = v != "0",
+            _ => (),
+        }
+    }
+}
+
+fn chromium(browser: Browser, root: &Path, profiles: &mut Vec<Profile>) {
+    let entries = match std::fs::read_dir(root) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name != "Default" && !name.starts_with("Profile ") {
+            continue;
+        }
+
+        // Newer versions moved the database into a subdirectory.
+        let dir = entry.path();
+        let cookies =
+            [dir.join("Network").join("Cookies"), dir.join("Cookies")]
+                .iter()
+                .find(|p| p.is_file())
+                .cloned();
+
+        if let Some(cookies) = cookies {
+            profiles.push(Profile {
+                browser,
+                name,
+                cookies,
+            });
+        }
+    }
+}
+
+/// Every browser profile with a cookie database that could be read.
+pub fn profiles() -> Result<Vec<Profile>, Error> {
+    let dirs = BaseDirs::new().context(error::HomeNotFound)?;
+    let home = dirs.home_dir();
+    let config = dirs.config_dir();
+    let flatpak = home.join(".var").join("app");
+
+    let mut profiles = Vec::new();
+
+    firefox(&home.join(".mozilla").join("firefox"), &mut profiles);
+    firefox(
+        &flatpak
+            .join("org.mozilla.firefox")
+            .join(".mozilla")
+            .join("firefox"),
+        &mut profiles,
+    );
+
+    let chromiums = [
+        (Browser::Chromium, config.join("chromium")),
+        (Browser::Chrome, config.join("google-chrome")),
+        (
+            Browser::Brave,
+            config.join("BraveSoftware").join("Brave-Browser"),
+        ),
+        (
+            Browser::Chromium,
+            flatpak
+                .join("org.chromium.Chromium")
+                .join("config")
+                .join("chromium"),
+        ),
+    ];
+
+    for (browser, root) in chromiums.iter() {
+        chromium(*browser, root, &mut profiles);
+    }
+
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, value: &str, default: bool, accessed: i64) -> Row {
+        Row {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            encrypted: false,
+            default,
+            accessed,
+        }
+    }
+
+    #[test]
+    fn assemble_needs_every_name() {
+        let rows = [row("a", "1", true, 0), row("a", "2", true, 1)];
+        let cookie = assemble(Browser::Firefox, &rows).unwrap();
+        assert_eq!(cookie, None);
+    }
+
+    #[test]
+    fn assemble_prefers_default_then_newest() {
+        let rows = [
+            row("b", "container", false, 9),
+            row("a", "old", true, 1),
+            row("b", "default", true, 2),
+            row("a", "new", true, 3),
+            row("c", "other", true, 4),
+        ];
+
+        let cookie = assemble(Browser::Firefox, &rows).unwrap();
+        assert_eq!(cookie.as_deref(), Some("a=new; b=default"));
+    }
+
+    #[test]
+    fn assemble_refuses_encrypted() {
+        let mut encrypted = row("a", "", true, 0);
+        encrypted.encrypted = true;
+
+        let rows = [encrypted, row("b", "2", true, 0)];
+        let result = assemble(Browser::Chrome, &rows);
+        assert!(matches!(result, Err(Error::Encrypted { .. })));
+    }
+
+    #[test]
+    fn firefox_reads_profiles_ini() {
+        let scratch = TempDir::new().unwrap();
+        let root = scratch.path().join("firefox");
+        let absolute = scratch.path().join("elsewhere");
+
+        for dir in &[root.join("abc.default"), absolute.clone()] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("cookies.sqlite"), b"").unwrap();
+        }
+        std::fs::create_dir_all(root.join("empty")).unwrap();
+
+        let ini = format!(
+            "[General]\nStartWithLastProfile=1\n\n\
+             [Profile0]\nName=default\nIsRelative=1\nPath=abc.default\n\n\
+             [Profile1]\nName=moved\nIsRelative=0\nPath={}\n\n\
+             [Profile2]\nName=missing\nIsRelative=1\nPath=empty\n",
+            absolute.display()
+        );
+        std::fs::write(root.join("profiles.ini"), ini).unwrap();
+
+        let mut profiles = Vec::new();
+        firefox(&root, &mut profiles);
+
+        let found: Vec<_> = profiles
+            .iter()
+            .map(|p| (p.name.as_str(), p.cookies.clone()))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                ("default", root.join("abc.default/cookies.sqlite")),
+                ("moved", absolute.join("cookies.sqlite")),
+            ]
+        );
+    }
+
+    #[test]
+    fn cookie_reads_firefox_database() {
+        let scratch = TempDir::new().unwrap();
+
+        // Characters that are special in paths or URIs shouldn't get in the
+        // way of finding the database.
+        let dir = scratch.path().join("odd #profile?");
+        std::fs::create_dir_all(&dir).unwrap();
+        let cookies = dir.join("cookies.sqlite");
+
+        let db = Connection::open(&cookies).unwrap();
+        db.execute_batch(
+            "CREATE TABLE moz_cookies (name, value, host, originAttributes, \
+                                       lastAccessed);
+             INSERT INTO moz_cookies VALUES
+                ('a', 'one', '.furaffinity.net', '', 5),
+                ('a', 'stale', '.furaffinity.net', '', 1),
+                ('b', 'private', '.furaffinity.net', '^privateBrowsingId=1', 9),
+                ('b', 'two', 'www.furaffinity.net', '', 2),
+                ('a', 'elsewhere', '.example.com', '', 9),
+                ('b', 'lookalike', 'notfuraffinity.net', '', 9),
+                ('b', 'lookalike', '.evilfuraffinity.net', '', 9);",
+        )
+        .unwrap();
+        drop(db);
+
+        let profile = Profile {
+            browser: Browser::Firefox,
+            name: "test".into(),
+            cookies,
+        };
+
+        let cookie = profile.cookie().unwrap();
+        assert_eq!(cookie.as_deref(), Some("a=one; b=two"));
+    }
+
+    #[test]
+    fn cookie_reads_write_ahead_log() {
+        let scratch = TempDir::new().unwrap();
+        let cookies = scratch.path().join("Cookies");
+
+        let db = Connection::open(&cookies).unwrap();
+        db.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA wal_autocheckpoint = 0;
+             CREATE TABLE cookies (name, value, encrypted_value, host_key, \
+                                   last_access_utc);
+             INSERT INTO cookies VALUES
+                ('a', 'one', x'', '.furaffinity.net', 1),
+                ('b', 'two', x'', 'furaffinity.net', 1);",
+        )
+        .unwrap();
+
+        // Still open, like a running browser, so nothing has left the log.
+        assert!(scratch.path().join("Cookies-wal").is_file());
+
+        let profile = Profile {
+            browser: Browser::Chromium,
+            name: "test".into(),
+            cookies,
+        };
+
+        let cookie = profile.cookie().unwrap();
+        assert_eq!(cookie.as_deref(), Some("a=one; b=two"));
+
+        drop(db);
+    }
+}
@@ -1,14 +1,19 @@
 extern crate libadwaita as adw;
 
-mod bridge;
+pub mod bridge;
+mod browser;
+mod link;
+mod logging;
 mod ptr;
 mod secrets;
+mod settings;
 mod ui;
 mod util;
 mod widgets;
 
 use crate::bridge::Bridge;
-use crate::secrets::{Secrets, SecretsExt};
+use crate::ui::background::Background;
+use crate::ui::inbox::Inbox;
 use crate::ui::root::Root;
 
 use gettextrs::TextDomain;
@@ -22,44 +27,96 @@ const QUALIFIER: &'static str = "dev.vypo.labrat-gtk";
 const ORGANIZATION: &'static str = "Labrat";
 
 pub fn main() {
+    logging::init();
+
     TextDomain::new("labrat")
         .push(std::env::current_dir().unwrap())
         .init()
         .ok();
 
-    let application =
-        gtk::Application::new(Some(QUALIFIER), Default::default());
+    let application = gtk::Application::new(
+        Some(QUALIFIER),
+        gio::ApplicationFlags::HANDLES_OPEN,
+    );
 
-    let bridge = Bridge::spawn();
+    let settings = settings::Store::load();
+    let bridge = Bridge::spawn(settings.get().network.config()).unwrap();
     let root_ui: Rc<RefCell<Option<ptr::Owned<Root>>>> = Default::default();
     let weak = Rc::downgrade(&root_ui);
+    let open_weak = Rc::downgrade(&root_ui);
+    let shutdown_weak = Rc::downgrade(&root_ui);
 
     let client = bridge.client();
-    let util = crate::util::Util::new(client).unwrap();
+    let util = crate::util::Util::new(client, settings).unwrap();
 
-    application.connect_activate(move |app| {
-        let clone = util.client().clone();
-        glib::MainContext::default().spawn(async move {
-            // TODO: Make login screen and move getting secrets onto a thread.
-            let secrets = Secrets::new().unwrap();
-            secrets.set("b=90f6c627-b546-493b-a3f3-47cfb8a11107; a=94ad66cd-03fc-40c3-9f66-65539769fb23").unwrap();
-            let cookie = secrets.get().unwrap().unwrap();
-            clone.login(&cookie).await.unwrap();
-        });
+    let inbox = Inbox::new(&application, util.clone());
+    let background = Background::new(&application, util.clone());
+
+    let inbox_weak = ptr::Owned::downgrade(&inbox);
+    let background_weak = ptr::Owned::downgrade(&background);
 
+    application.connect_activate(move |app| {
         if let Some(cell) = weak.upgrade() {
-            if cell.borrow().is_some() {
+            if let Some(ref root) = *cell.borrow() {
+                root.window().present();
                 return;
             }
 
             let root = Root::new(app, util.clone()).unwrap();
             root.show();
 
+            if let Some(inbox) = inbox_weak.upgrade() {
+                inbox.set_root(ptr::Owned::downgrade(&root));
+            }
+
+            // While the app keeps running, hide the window instead so the
+            // same one comes back on the next activation.
+            let inbox = inbox_weak.clone();
+            let background = background_weak.clone();
+            root.window().connect_close_request(move |window| {
+                let checking = inbox.upgrade().map(|i| i.is_checking());
+                let enabled = background.upgrade().map(|b| b.is_enabled());
+
+                if checking == Some(true) || enabled == Some(true) {
+                    window.hide();
+                    glib::signal::Inhibit(true)
+                } else {
+                    glib::signal::Inhibit(false)
+                }
+            });
+
+            root.start();
+
             *cell.borrow_mut() = Some(root);
         }
     });
 
+    // Links, from the browser or `labrat://` URIs, open in the window.
+    application.connect_open(move |app, files, _| {
+        app.activate();
+
+        if let Some(cell) = open_weak.upgrade() {
+            if let Some(ref root) = *cell.borrow() {
+                for file in files {
+                    root.open_uri(&file.uri());
+                }
+            }
+        }
+    });
+
+    // Quitting doesn't close the window first, so its state is saved here
+    // as well as when it's closed.
+    application.connect_shutdown(move |_| {
+        if let Some(cell) = shutdown_weak.upgrade() {
+            if let Some(ref root) = *cell.borrow() {
+                root.save_state();
+            }
+        }
+    });
+
     application.run();
+    drop(background);
+    drop(inbox);
     drop(root_ui);
     bridge.join();
 }
@@ -0,0 +1,68 @@
+//! Links to pages on the site, either as `https://www.furaffinity.net/…`
+//! URLs or with the app's own `labrat://` scheme, where `labrat://view/1234`
+//! means `https://www.furaffinity.net/view/1234/`.
+
+use labrat::keys::{GalleryKey, JournalKey, UserKey, ViewKey};
+
+use reqwest::Url;
+
+use std::convert::TryFrom;
+
+pub const SCHEME: &str = "labrat";
+
+const SITE: &str = "https://www.furaffinity.net/";
+const HOSTS: &[&str] = &["www.furaffinity.net", "furaffinity.net"];
+
+#[derive(Debug, Clone)]
+pub enum Link {
+    View(ViewKey),
+    Journal(JournalKey),
+    User(UserKey),
+    Gallery(GalleryKey),
+}
+
+impl Link {
+    pub fn parse(uri: &str) -> Option<Self> {
+        let url = web_url(uri)?;
+
+        let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+
+        match (segments.next()?, segments.next()?) {
+            ("view", _) => ViewKey::try_from(url).ok().map(Link::View),
+            ("journal", _) => JournalKey::try_from(url).ok().map(Link::Journal),
+            ("user", _) => UserKey::try_from(url).ok().map(Link::User),
+            ("gallery", _) => GalleryKey::try_from(url).ok().map(Link::Gallery),
+            _ => None,
+        }
+    }
+}
+
+/// The page for a submission on the site.
+pub fn view_url(key: &ViewKey) -> Url {
+    let mut url = Url::parse(SITE).unwrap();
+    url.set_path(&format!("/view/{}/", key.view_id));
+    url
+}
+
+/// `uri` as a URL on the site, if it points there at all.
+pub fn web_url(uri: &str) -> Option<Url> {
+    let url = Url::parse(uri).ok()?;
+
+    match url.scheme() {
+        SCHEME => {
+            let mut path = format!("/{}{}", url.host_str()?, url.path());
+            if !path.ends_with('/') {
+                path.push('/');
+            }
+
+            let mut web = Url::parse(SITE).ok()?;
+            web.set_path(&path);
+            web.set_query(url.query());
+            Some(web)
+        }
+
+        "http" | "https" if HOSTS.contains(&url.host_str()?) => Some(url),
+
+        _ => None,
+    }
+}
@@ -0,0 +1,114 @@
+//! Logging through GLib, so messages end up in the journal alongside GTK's.
+//!
+//! Each module logs under its own target (like `labrat_gtk::bridge`), which
+//! becomes the GLib log domain. `G_MESSAGES_DEBUG` can name the domains to
+//! show debug messages for, or the toggle in the app menu shows them all.
+
+use glib::{GlibLogger, GlibLoggerDomain, GlibLoggerFormat};
+use glib::{LogField, LogLevel, LogWriterOutput};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Passes messages on to GLib, writing debug messages itself while the
+/// toggle is on.
+///
+/// GLib's default writer drops debug messages unless their domain is listed
+/// in `G_MESSAGES_DEBUG`, and changing the environment once threads are
+/// running isn't safe, so those skip straight to the writers it would have
+/// used.
+struct Logger {
+    glib: GlibLogger,
+    debug: AtomicBool,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let forced = record.level() >= Level::Debug
+            && self.debug.load(Ordering::Relaxed);
+
+        if forced {
+            write_debug(record);
+        } else {
+            self.glib.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.glib.flush();
+    }
+}
+
+/// Write `record` to the journal, or to stderr if that isn't where stderr
+/// goes, as GLib's default writer would if debug messages weren't dropped.
+fn write_debug(record: &Record) {
+    let message = record.args().to_string();
+    let line = record.line().map(|l| l.to_string());
+
+    let mut fields = vec![
+        LogField::new("PRIORITY", b"7"),
+        LogField::new("GLIB_DOMAIN", record.target().as_bytes()),
+        LogField::new("MESSAGE", message.as_bytes()),
+    ];
+
+    if let Some(file) = record.file() {
+        fields.push(LogField::new("CODE_FILE", file.as_bytes()));
+    }
+
+    if let Some(ref line) = line {
+        fields.push(LogField::new("CODE_LINE", line.as_bytes()));
+    }
+
+    if glib::log_writer_is_journald(2) {
+        let output = glib::log_writer_journald(LogLevel::Debug, &fields);
+        if output == LogWriterOutput::Handled {
+            return;
+        }
+    }
+
+    glib::log_writer_standard_streams(LogLevel::Debug, &fields);
+}
+
+static LOGGER: Logger = Logger {
+    glib: GlibLogger::new(
+        GlibLoggerFormat::Structured,
+        GlibLoggerDomain::CrateTarget,
+    ),
+    debug: AtomicBool::new(false),
+};
+
+pub fn init() {
+    log::set_logger(&LOGGER).ok();
+
+    let level = match std::env::var_os("G_MESSAGES_DEBUG") {
+        Some(_) => LevelFilter::Debug,
+        None => LevelFilter::Info,
+    };
+
+    log::set_max_level(level);
+}
+
+pub fn is_debug() -> bool {
+    log::max_level() >= LevelFilter::Debug
+}
+
+pub fn set_debug(enabled: bool) {
+    LOGGER.debug.store(enabled, Ordering::Relaxed);
+
+    if enabled {
+        log::set_max_level(LevelFilter::Debug);
+    } else {
+        log::set_max_level(LevelFilter::Info);
+    }
+
+    log::info!("debug logging {}", if enabled { "on" } else { "off" });
+}
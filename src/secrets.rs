@@ -1,14 +1,13 @@
+mod file;
+
 #[cfg(target_os = "linux")]
 mod linux;
 
-#[cfg(not(target_os = "linux"))]
-mod fake;
+use crate::util::Util;
 
-#[cfg(target_os = "linux")]
-pub use self::linux::*;
+use directories::ProjectDirs;
 
-#[cfg(not(target_os = "linux"))]
-pub use self::fake::*;
+use serde::{Deserialize, Serialize};
 
 use std::borrow::Cow;
 use std::fmt;
@@ -17,6 +16,7 @@ use std::fmt;
 pub struct Error {
     message: Cow<'static, str>,
     source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    locked: bool,
 }
 
 impl Error {
@@ -25,9 +25,22 @@ impl Error {
         Self {
             message: message.into(),
             source: None,
+            locked: false,
         }
     }
 
+    fn locked() -> Self {
+        Self {
+            locked: true,
+            ..Self::new("secrets are locked")
+        }
+    }
+
+    /// Whether the secrets need to be unlocked with a passphrase first.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
     #[allow(unused)]
     fn with_source<S, E>(message: S, source: E) -> Self
     where
@@ -37,6 +50,7 @@ impl Error {
         Self {
             message: message.into(),
             source: Some(Box::new(source)),
+            locked: false,
         }
     }
 }
@@ -62,10 +76,197 @@ impl std::error::Error for Error {
     }
 }
 
+/// Storage for the cookies of one or more named accounts.
 pub trait SecretsExt: Sized {
     fn new() -> Result<Self, Error>;
 
-    fn set(&self, cookie: &str) -> Result<(), Error>;
-    fn get(&self) -> Result<Option<String>, Error>;
-    fn clear(&self) -> Result<(), Error>;
+    /// Names of every account with a stored cookie.
+    fn accounts(&self) -> Result<Vec<String>, Error>;
+
+    fn set(&self, account: &str, cookie: &str) -> Result<(), Error>;
+    fn get(&self, account: &str) -> Result<Option<String>, Error>;
+    fn clear(&self, account: &str) -> Result<(), Error>;
+}
+
+fn dirs() -> Result<ProjectDirs, Error> {
+    ProjectDirs::from(
+        crate::QUALIFIER,
+        crate::ORGANIZATION,
+        env!("CARGO_PKG_NAME"),
+    )
+    .ok_or_else(|| Error::new("unable to find project directories"))
+}
+
+/// Where cookies are stored, chosen in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    /// The Secret Service if it's available, otherwise the encrypted file.
+    Auto,
+    SecretService,
+    File,
+}
+
+impl Backend {
+    pub const ALL: &'static [Backend] =
+        &[Backend::Auto, Backend::SecretService, Backend::File];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Backend::Auto => "auto",
+            Backend::SecretService => "secret-service",
+            Backend::File => "file",
+        }
+    }
+
+    pub fn from_str(text: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|b| b.as_str() == text)
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::Auto
+    }
+}
+
+/// Whichever backend was chosen, see [`Backend`].
+pub enum Secrets {
+    #[cfg(target_os = "linux")]
+    SecretService(linux::Secrets),
+    File(file::Secrets),
+}
+
+impl Secrets {
+    /// Whether `unlock` has to be called before the secrets can be used.
+    pub fn needs_unlock(&self) -> bool {
+        match self {
+            #[cfg(target_os = "linux")]
+            Secrets::SecretService(_) => false,
+            Secrets::File(f) => f.is_locked(),
+        }
+    }
+
+    /// Unlock with `passphrase`. Slow, so keep it off the main thread.
+    pub fn unlock(&self, passphrase: &str) -> Result<(), Error> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Secrets::SecretService(_) => Ok(()),
+            Secrets::File(f) => f.unlock(passphrase),
+        }
+    }
+
+    /// Clean up duplicate and outdated items, which only the Secret Service
+    /// can end up with.
+    pub fn tidy(&self) -> Result<(), Error> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Secrets::SecretService(s) => s.tidy(),
+            Secrets::File(_) => Ok(()),
+        }
+    }
+
+    /// Open the backend chosen in the settings and call `func` with it on
+    /// the thread pool, since the Secret Service blocks on D-Bus.
+    pub async fn with<F, T>(util: &Util, func: F) -> Result<T, Error>
+    where
+        F: 'static + Send + FnOnce(&Secrets) -> Result<T, Error>,
+        T: 'static + Send,
+    {
+        let backend = util.settings().get().secrets.backend;
+
+        util.spawn_background(move || {
+            Self::open(backend).and_then(|s| func(&s))
+        })
+        .await
+    }
+
+    fn open(backend: Backend) -> Result<Self, Error> {
+        match backend {
+            Backend::File => Ok(Secrets::File(file::Secrets::new()?)),
+
+            #[cfg(target_os = "linux")]
+            Backend::SecretService => {
+                Ok(Secrets::SecretService(linux::Secrets::new()?))
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            Backend::SecretService => {
+                Err(Error::new("secret service not supported"))
+            }
+
+            #[cfg(target_os = "linux")]
+            Backend::Auto => match linux::Secrets::new() {
+                Ok(s) => Ok(Secrets::SecretService(s)),
+                Err(e) => {
+                    log::warn!("falling back to secrets file: {}", e);
+                    Ok(Secrets::File(file::Secrets::new()?))
+                }
+            },
+
+            #[cfg(not(target_os = "linux"))]
+            Backend::Auto => Ok(Secrets::File(file::Secrets::new()?)),
+        }
+    }
+
+    fn inner(&self) -> &dyn Store {
+        match self {
+            #[cfg(target_os = "linux")]
+            Secrets::SecretService(s) => s,
+            Secrets::File(f) => f,
+        }
+    }
+}
+
+/// Object safe part of [`SecretsExt`], for dispatching to a backend.
+trait Store {
+    fn accounts(&self) -> Result<Vec<String>, Error>;
+    fn set(&self, account: &str, cookie: &str) -> Result<(), Error>;
+    fn get(&self, account: &str) -> Result<Option<String>, Error>;
+    fn clear(&self, account: &str) -> Result<(), Error>;
+}
+
+impl<T> Store for T
+where
+    T: SecretsExt,
+{
+    fn accounts(&self) -> Result<Vec<String>, Error> {
+        SecretsExt::accounts(self)
+    }
+
+    fn set(&self, account: &str, cookie: &str) -> Result<(), Error> {
+        SecretsExt::set(self, account, cookie)
+    }
+
+    fn get(&self, account: &str) -> Result<Option<String>, Error> {
+        SecretsExt::get(self, account)
+    }
+
+    fn clear(&self, account: &str) -> Result<(), Error> {
+        SecretsExt::clear(self, account)
+    }
+}
+
+impl SecretsExt for Secrets {
+    /// The default backend, whatever the settings say; see
+    /// [`Secrets::with`].
+    fn new() -> Result<Self, Error> {
+        Self::open(Backend::default())
+    }
+
+    fn accounts(&self) -> Result<Vec<String>, Error> {
+        self.inner().accounts()
+    }
+
+    fn set(&self, account: &str, cookie: &str) -> Result<(), Error> {
+        self.inner().set(account, cookie)
+    }
+
+    fn get(&self, account: &str) -> Result<Option<String>, Error> {
+        self.inner().get(account)
+    }
+
+    fn clear(&self, account: &str) -> Result<(), Error> {
+        self.inner().clear(account)
+    }
 }
@@ -0,0 +1,294 @@
+//! Cookies stored in a file encrypted with a key derived from a passphrase,
+//! for systems without a Secret Service.
+//!
+//! The file is laid out as `MAGIC`, the scrypt salt, the nonce, and then the
+//! encrypted accounts, one `account\tcookie` pair per line.
+
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use once_cell::sync::Lazy;
+
+use rand::RngCore;
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::Error;
+
+const MAGIC: &[u8] = b"LABRAT-SECRETS-1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+#[derive(Debug)]
+struct Unlocked {
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+}
+
+/// Key for the file, kept for as long as the app is running once unlocked.
+static UNLOCKED: Lazy<Mutex<Option<Unlocked>>> = Lazy::new(|| Mutex::new(None));
+
+fn derive(
+    passphrase: &str,
+    salt: &[u8],
+    params: &scrypt::Params,
+) -> Result<[u8; 32], Error> {
+    let mut key = [0; 32];
+
+    scrypt::scrypt(passphrase.as_bytes(), salt, params, &mut key)
+        .map_err(|_| Error::new("unable to derive key"))?;
+
+    Ok(key)
+}
+
+fn read(path: &PathBuf) -> Result<Option<Vec<u8>>, Error> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::with_source("unable to read secrets file", e)),
+    }
+}
+
+fn decrypt(key: &[u8; 32], bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+    if bytes.len() < header || !bytes.starts_with(MAGIC) {
+        return Err(Error::new("secrets file is corrupt"));
+    }
+
+    let nonce = &bytes[MAGIC.len() + SALT_LEN..header];
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+
+    cipher
+        .decrypt(XNonce::from_slice(nonce), &bytes[header..])
+        .map_err(|_| Error::new("wrong passphrase"))
+}
+
+/// Encrypt `plain` into the contents of a secrets file, see the module
+/// documentation.
+fn encrypt(
+    key: &[u8; 32],
+    salt: &[u8; SALT_LEN],
+    plain: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut nonce = [0; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let encrypted = cipher
+        .encrypt(XNonce::from_slice(&nonce), plain)
+        .map_err(|_| Error::new("unable to encrypt secrets"))?;
+
+    let mut bytes = Vec::with_capacity(
+        MAGIC.len() + SALT_LEN + NONCE_LEN + encrypted.len(),
+    );
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(salt);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&encrypted);
+
+    Ok(bytes)
+}
+
+fn salt_of(bytes: &[u8]) -> Result<[u8; SALT_LEN], Error> {
+    let mut salt = [0; SALT_LEN];
+
+    let found = bytes
+        .get(MAGIC.len()..MAGIC.len() + SALT_LEN)
+        .ok_or_else(|| Error::new("secrets file is corrupt"))?;
+
+    salt.copy_from_slice(found);
+    Ok(salt)
+}
+
+/// Secrets stored in an encrypted file under the config directory.
+#[derive(Debug)]
+pub struct Secrets {
+    path: PathBuf,
+}
+
+impl Secrets {
+    pub fn is_locked(&self) -> bool {
+        UNLOCKED.lock().unwrap().is_none()
+    }
+
+    /// Derive the key from `passphrase`, checking it against the existing
+    /// file if there is one.
+    ///
+    /// This is slow on purpose, so it shouldn't be run on the main thread.
+    pub fn unlock(&self, passphrase: &str) -> Result<(), Error> {
+        let existing = read(&self.path)?;
+
+        let salt = match existing {
+            Some(ref bytes) => salt_of(bytes)?,
+            None => {
+                let mut salt = [0; SALT_LEN];
+                rand::thread_rng().fill_bytes(&mut salt);
+                salt
+            }
+        };
+
+        let key = derive(passphrase, &salt, &scrypt::Params::recommended())?;
+
+        if let Some(bytes) = existing {
+            decrypt(&key, &bytes)?;
+        }
+
+        *UNLOCKED.lock().unwrap() = Some(Unlocked { salt, key });
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<(String, String)>, Error> {
+        let unlocked = UNLOCKED.lock().unwrap();
+        let unlocked = unlocked.as_ref().ok_or_else(Error::locked)?;
+
+        let bytes = match read(&self.path)? {
+            Some(b) => b,
+            None => return Ok(Vec::new()),
+        };
+
+        let plain = decrypt(&unlocked.key, &bytes)?;
+        let plain = String::from_utf8(plain)
+            .map_err(|e| Error::with_source("unable to decode secrets", e))?;
+
+        let entries = plain
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, '\t');
+                let account = parts.next()?;
+                let cookie = parts.next()?;
+                Some((account.to_owned(), cookie.to_owned()))
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn store(&self, entries: &[(String, String)]) -> Result<(), Error> {
+        let unlocked = UNLOCKED.lock().unwrap();
+        let unlocked = unlocked.as_ref().ok_or_else(Error::locked)?;
+
+        let mut plain = String::new();
+        for (account, cookie) in entries {
+            plain.push_str(account);
+            plain.push('\t');
+            plain.push_str(cookie);
+            plain.push('\n');
+        }
+
+        let bytes = encrypt(&unlocked.key, &unlocked.salt, plain.as_bytes())?;
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                Error::with_source("unable to create config directory", e)
+            })?;
+        }
+
+        // Write next to the real file and rename, so a crash can't leave a
+        // truncated file behind.
+        let temp = self.path.with_extension("tmp");
+        std::fs::write(&temp, bytes)
+            .and_then(|_| std::fs::rename(&temp, &self.path))
+            .map_err(|e| {
+                Error::with_source("unable to write secrets file", e)
+            })?;
+
+        Ok(())
+    }
+}
+
+impl super::SecretsExt for Secrets {
+    fn new() -> Result<Self, Error> {
+        let path = super::dirs()?.config_dir().join("secrets");
+        Ok(Self { path })
+    }
+
+    fn accounts(&self) -> Result<Vec<String>, Error> {
+        let mut accounts: Vec<_> =
+            self.load()?.into_iter().map(|(a, _)| a).collect();
+        accounts.sort();
+        Ok(accounts)
+    }
+
+    fn get(&self, account: &str) -> Result<Option<String>, Error> {
+        let found = self
+            .load()?
+            .into_iter()
+            .find(|(a, _)| a == account)
+            .map(|(_, c)| c);
+
+        Ok(found)
+    }
+
+    fn set(&self, account: &str, cookie: &str) -> Result<(), Error> {
+        let invalid = |s: &str| s.contains(|c| c == '\t' || c == '\n');
+        if invalid(account) || invalid(cookie) {
+            return Err(Error::new(
+                "account or cookie contains a tab or newline",
+            ));
+        }
+
+        let mut entries = self.load()?;
+        entries.retain(|(a, _)| a != account);
+        entries.push((account.to_owned(), cookie.to_owned()));
+
+        self.store(&entries)
+    }
+
+    fn clear(&self, account: &str) -> Result<(), Error> {
+        let mut entries = self.load()?;
+        entries.retain(|(a, _)| a != account);
+
+        self.store(&entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT: [u8; SALT_LEN] = [7; SALT_LEN];
+    const PLAIN: &[u8] = b"account\ta=1; b=2\n";
+
+    /// Key for `passphrase`, derived with cheap parameters so tests run
+    /// quickly.
+    fn key(passphrase: &str) -> [u8; 32] {
+        let params = scrypt::Params::new(4, 8, 1).unwrap();
+        derive(passphrase, &SALT, &params).unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let key = key("right");
+        let bytes = encrypt(&key, &SALT, PLAIN).unwrap();
+
+        assert!(bytes.starts_with(MAGIC));
+        assert_eq!(salt_of(&bytes).unwrap(), SALT);
+        assert_eq!(decrypt(&key, &bytes).unwrap(), PLAIN);
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let bytes = encrypt(&key("right"), &SALT, PLAIN).unwrap();
+        let error = decrypt(&key("wrong"), &bytes).unwrap_err();
+        assert_eq!(error.to_string(), "wrong passphrase");
+    }
+
+    #[test]
+    fn tampering_is_rejected() {
+        let key = key("right");
+        let bytes = encrypt(&key, &SALT, PLAIN).unwrap();
+
+        // Any byte of the nonce or ciphertext.
+        for index in MAGIC.len() + SALT_LEN..bytes.len() {
+            let mut tampered = bytes.clone();
+            tampered[index] ^= 1;
+            assert!(decrypt(&key, &tampered).is_err(), "byte {}", index);
+        }
+
+        let truncated = &bytes[..MAGIC.len() + SALT_LEN];
+        assert!(decrypt(&key, truncated).is_err());
+    }
+}
@@ -1,9 +1,12 @@
-use secret_service::{Collection, EncryptionType, SecretService};
+use secret_service::{Collection, EncryptionType, Item, SecretService};
 
 use std::collections::HashMap;
 
 use super::Error;
 
+/// Account that cookies stored before accounts had names are moved to.
+const LEGACY_ACCOUNT: &str = "default";
+
 pub struct Secrets {
     service: SecretService<'static>,
 }
@@ -23,35 +26,63 @@ impl Secrets {
         Ok(collection)
     }
 
-    fn attributes() -> HashMap<&'static str, &'static str> {
+    fn label(account: &str) -> String {
+        format!("Labrat cookie for {}", account)
+    }
+
+    /// Attributes every item stored by any version of the app has.
+    fn all_attributes() -> HashMap<&'static str, &'static str> {
         let mut map = HashMap::with_capacity(1);
         map.insert("labrat", "cookie");
         map
     }
-}
 
-impl super::SecretsExt for Secrets {
-    fn new() -> Result<Self, Error> {
-        let service = SecretService::new(EncryptionType::Dh).map_err(|e| {
-            Error::with_source("unable to create secret service", e)
-        })?;
+    fn attributes(account: &str) -> HashMap<&str, &str> {
+        let mut map = Self::all_attributes();
+        map.insert("application", crate::QUALIFIER);
+        map.insert("account", account);
+        map
+    }
 
-        Ok(Self { service })
+    fn search<'a>(
+        collection: &'a Collection,
+        attributes: HashMap<&str, &str>,
+    ) -> Result<Vec<Item<'a>>, Error> {
+        collection
+            .search_items(attributes)
+            .map_err(|e| Error::with_source("unable to search collection", e))
     }
 
-    fn get(&self) -> Result<Option<String>, Error> {
-        let collection = self.collection()?;
+    /// Items for `account`, most recently modified first.
+    ///
+    /// Searching without the application attribute also finds items stored
+    /// before it was added.
+    fn items<'a>(
+        collection: &'a Collection,
+        account: &str,
+    ) -> Result<Vec<Item<'a>>, Error> {
+        let mut search = Self::all_attributes();
+        search.insert("account", account);
+
+        let mut found = Self::search(collection, search)?;
+        found.sort_by_key(|i| std::cmp::Reverse(i.get_modified().ok()));
+        Ok(found)
+    }
 
-        let found =
-            collection.search_items(Self::attributes()).map_err(|e| {
-                Error::with_source("unable to search collection", e)
-            })?;
+    fn account_of(item: &Item) -> Result<Option<String>, Error> {
+        let attributes = item.get_attributes().map_err(|e| {
+            Error::with_source("unable to get item attributes", e)
+        })?;
 
-        let item = match found.first() {
-            Some(i) => i,
-            None => return Ok(None),
-        };
+        let account = attributes
+            .into_iter()
+            .find(|(k, _)| k == "account")
+            .map(|(_, v)| v);
+
+        Ok(account)
+    }
 
+    fn secret(item: &Item) -> Result<String, Error> {
         if let Ok(true) = item.is_locked() {
             item.unlock()
                 .map_err(|e| Error::with_source("unable to unlock item", e))?;
@@ -60,37 +91,67 @@ impl super::SecretsExt for Secrets {
         let secret_bytes = item
             .get_secret()
             .map_err(|e| Error::with_source("unable to get secret", e))?;
-        let secret = String::from_utf8(secret_bytes)
-            .map_err(|e| Error::with_source("unable to decode secret", e))?;
 
-        Ok(Some(secret))
+        String::from_utf8(secret_bytes)
+            .map_err(|e| Error::with_source("unable to decode secret", e))
     }
 
-    fn set(&self, cookie: &str) -> Result<(), Error> {
-        let collection = self.collection()?;
+    /// Give `item` the current label and attributes for `account`.
+    fn adopt(item: &Item, account: &str) -> Result<(), Error> {
+        item.set_label(&Self::label(account))
+            .and_then(|_| item.set_attributes(Self::attributes(account)))
+            .map_err(|e| Error::with_source("unable to update item", e))
+    }
 
-        collection
-            .create_item(
-                "cookie",
-                Self::attributes(),
-                cookie.as_bytes(),
-                true,
-                "text/plain",
-            )
-            .map_err(|e| Error::with_source("unable to create item", e))?;
+    fn delete(items: &[Item]) -> Result<(), Error> {
+        for item in items {
+            item.delete().map_err(|e| {
+                Error::with_source("unable to delete secret", e)
+            })?;
+        }
 
         Ok(())
     }
 
-    fn clear(&self) -> Result<(), Error> {
+    /// Leave exactly one item per account, labelled and tagged like `set`
+    /// would, and move items stored before accounts had names to
+    /// `LEGACY_ACCOUNT`.
+    pub fn tidy(&self) -> Result<(), Error> {
         let collection = self.collection()?;
+        let found = Self::search(&collection, Self::all_attributes())?;
 
-        let found =
-            collection.search_items(Self::attributes()).map_err(|e| {
-                Error::with_source("unable to search collection", e)
-            })?;
+        let mut accounts = Vec::new();
+        let mut legacy = Vec::new();
+
+        for item in &found {
+            match Self::account_of(item)? {
+                Some(a) if !accounts.contains(&a) => accounts.push(a),
+                Some(_) => (),
+                None => legacy.push(item),
+            }
+        }
+
+        for account in &accounts {
+            let items = Self::items(&collection, account)?;
+
+            if let Some((keep, extra)) = items.split_first() {
+                Self::adopt(keep, account)?;
+                Self::delete(extra)?;
+            }
+        }
+
+        if legacy.is_empty() {
+            return Ok(());
+        }
+
+        legacy.sort_by_key(|i| std::cmp::Reverse(i.get_modified().ok()));
 
-        if let Some(item) = found.first() {
+        if !accounts.iter().any(|a| a == LEGACY_ACCOUNT) {
+            let newest = Self::secret(legacy[0])?;
+            super::SecretsExt::set(self, LEGACY_ACCOUNT, &newest)?;
+        }
+
+        for item in legacy {
             item.delete().map_err(|e| {
                 Error::with_source("unable to delete secret", e)
             })?;
@@ -99,3 +160,78 @@ impl super::SecretsExt for Secrets {
         Ok(())
     }
 }
+
+impl super::SecretsExt for Secrets {
+    fn new() -> Result<Self, Error> {
+        let service = SecretService::new(EncryptionType::Dh).map_err(|e| {
+            Error::with_source("unable to create secret service", e)
+        })?;
+
+        Ok(Self { service })
+    }
+
+    fn accounts(&self) -> Result<Vec<String>, Error> {
+        let collection = self.collection()?;
+        let found = Self::search(&collection, Self::all_attributes())?;
+
+        let mut accounts = Vec::with_capacity(found.len());
+
+        for item in found {
+            if let Some(account) = Self::account_of(&item)? {
+                if !accounts.contains(&account) {
+                    accounts.push(account);
+                }
+            }
+        }
+
+        accounts.sort();
+        Ok(accounts)
+    }
+
+    fn get(&self, account: &str) -> Result<Option<String>, Error> {
+        let collection = self.collection()?;
+
+        // `set` and `tidy` keep this to one item, but read the newest in case
+        // another copy of the app raced us.
+        match Self::items(&collection, account)?.first() {
+            Some(item) => Self::secret(item).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn set(&self, account: &str, cookie: &str) -> Result<(), Error> {
+        let collection = self.collection()?;
+        let items = Self::items(&collection, account)?;
+
+        // Update the existing item in place, so it keeps any permissions the
+        // user gave it in their keyring manager.
+        let (keep, extra) = match items.split_first() {
+            Some(split) => split,
+            None => {
+                collection
+                    .create_item(
+                        &Self::label(account),
+                        Self::attributes(account),
+                        cookie.as_bytes(),
+                        true,
+                        "text/plain",
+                    )
+                    .map_err(|e| {
+                        Error::with_source("unable to create item", e)
+                    })?;
+
+                return Ok(());
+            }
+        };
+
+        keep.set_secret(cookie.as_bytes(), "text/plain")
+            .map_err(|e| Error::with_source("unable to update secret", e))?;
+        Self::adopt(keep, account)?;
+        Self::delete(extra)
+    }
+
+    fn clear(&self, account: &str) -> Result<(), Error> {
+        let collection = self.collection()?;
+        Self::delete(&Self::items(&collection, account)?)
+    }
+}
@@ -0,0 +1,476 @@
+//! Preferences, stored as TOML in `settings.toml` under the config
+//! directory.
+//!
+//! Everything goes through a [`Store`], which saves changes and tells
+//! subscribers about them, so a setting changed in the preferences window
+//! takes effect wherever it's used.
+
+use crate::bridge::Config;
+use crate::secrets::Backend;
+
+use directories::ProjectDirs;
+
+use labrat::resources::{PreviewSize, Rating, Submission};
+
+use serde::{Deserialize, Serialize};
+
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use tokio::sync::watch;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("unable to find project directories"))]
+    Directories,
+
+    #[snafu(display("unable to read {}: {}", path.display(), source))]
+    Read { path: PathBuf, source: io::Error },
+
+    #[snafu(display("unable to parse {}: {}", path.display(), source))]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[snafu(display("unable to serialize settings: {}", source))]
+    Serialize { source: toml::ser::Error },
+
+    #[snafu(display("unable to write {}: {}", path.display(), source))]
+    Write { path: PathBuf, source: io::Error },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThumbnailSize {
+    Small,
+    Medium,
+    Large,
+    ExtraLarge,
+}
+
+impl ThumbnailSize {
+    pub const ALL: &'static [Self] =
+        &[Self::Small, Self::Medium, Self::Large, Self::ExtraLarge];
+
+    pub fn preview_size(self) -> PreviewSize {
+        match self {
+            Self::Small => PreviewSize::S,
+            Self::Medium => PreviewSize::M,
+            Self::Large => PreviewSize::L,
+            Self::ExtraLarge => PreviewSize::Xl,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    List,
+    Grid,
+}
+
+/// Which submissions by the same artist to collapse into one row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Collapse {
+    Off,
+
+    /// Only submissions that follow one another in the inbox.
+    Consecutive,
+    All,
+}
+
+/// What to do with submissions of a particular rating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Visibility {
+    Show,
+
+    /// Show blurred until clicked.
+    Blur,
+    Hide,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Inbox {
+    pub thumbnail_size: ThumbnailSize,
+    pub layout: Layout,
+    pub collapse: Collapse,
+
+    /// How close to the end of the list, in pixels, to start loading the
+    /// next page.
+    pub load_ahead: u32,
+}
+
+impl Default for Inbox {
+    fn default() -> Self {
+        Self {
+            thumbnail_size: ThumbnailSize::ExtraLarge,
+            layout: Layout::List,
+            collapse: Collapse::Off,
+            load_ahead: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Content {
+    pub general: Visibility,
+    pub mature: Visibility,
+    pub adult: Visibility,
+
+    /// Blur anything that isn't rated general, whatever the choices above
+    /// say, short of hiding it.
+    pub safe_mode: bool,
+}
+
+impl Content {
+    pub fn visibility(&self, rating: Rating) -> Visibility {
+        let chosen = match rating {
+            Rating::General => self.general,
+            Rating::Mature => self.mature,
+            Rating::Adult => self.adult,
+        };
+
+        match (chosen, rating) {
+            (Visibility::Show, Rating::Mature)
+            | (Visibility::Show, Rating::Adult)
+                if self.safe_mode =>
+            {
+                Visibility::Blur
+            }
+            (v, _) => v,
+        }
+    }
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Self {
+            general: Visibility::Show,
+            mature: Visibility::Show,
+            adult: Visibility::Show,
+            safe_mode: false,
+        }
+    }
+}
+
+/// Submissions to keep out of the inbox.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Blocklist {
+    pub artists: Vec<String>,
+
+    /// Words that block a submission when its title contains them, or its
+    /// tags do once they're known.
+    pub keywords: Vec<String>,
+
+    /// Whether to also remove blocked submissions from the inbox on the
+    /// site.
+    pub auto_clear: bool,
+}
+
+impl Blocklist {
+    pub fn blocks_artist(&self, name: &str) -> bool {
+        self.artists.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether `submission` is blocked, checking `tags` too if they've been
+    /// fetched from its page.
+    ///
+    /// Listings don't include tags, so without them only the title is
+    /// checked.
+    pub fn blocks(
+        &self,
+        submission: &Submission,
+        tags: Option<&[String]>,
+    ) -> bool {
+        if self.blocks_artist(submission.artist().name()) {
+            return true;
+        }
+
+        let title = submission.title().to_lowercase();
+        let tags = tags.unwrap_or_default();
+
+        self.keywords
+            .iter()
+            .filter(|k| !k.is_empty())
+            .map(|k| k.to_lowercase())
+            .any(|k| {
+                title.contains(&k) || tags.iter().any(|t| t.to_lowercase() == k)
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Cache {
+    /// Largest the image cache of each account may grow, in megabytes, up
+    /// to [`Cache::MAX_SIZE`].
+    pub max_size: u32,
+
+    /// Directory to keep cached images in, instead of the usual cache
+    /// directory. Read when the app starts.
+    pub location: Option<PathBuf>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            max_size: 512,
+            location: None,
+        }
+    }
+}
+
+impl Cache {
+    /// Largest `max_size` that fits, since libsoup takes the size in bytes
+    /// as a `u32`.
+    pub const MAX_SIZE: u32 = u32::MAX / (1024 * 1024);
+
+    pub fn max_bytes(&self) -> u32 {
+        self.max_size.min(Self::MAX_SIZE) * 1024 * 1024
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Notifications {
+    /// Minutes between inbox checks, or zero to not check.
+    pub poll_interval: u32,
+
+    /// Whether to show a notification when a check finds something new.
+    pub notify: bool,
+
+    pub run_in_background: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            poll_interval: 0,
+            notify: true,
+            run_in_background: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Network {
+    /// Requests in flight at once. Read when the bridge starts.
+    pub max_concurrent: u32,
+
+    /// Requests started per second, or zero for no limit. Read when the
+    /// bridge starts.
+    pub requests_per_second: f64,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            max_concurrent: config.max_concurrent as u32,
+            requests_per_second: config.requests_per_second,
+        }
+    }
+}
+
+impl Network {
+    pub fn config(&self) -> Config {
+        Config {
+            max_concurrent: self.max_concurrent.max(1) as usize,
+            requests_per_second: self.requests_per_second.max(0.),
+            ..Config::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Secrets {
+    /// Where cookies are stored. Cookies aren't moved when this changes.
+    pub backend: Backend,
+}
+
+/// How the window was left, so it can come back the same way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Window {
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+
+    /// Width of the inbox next to the page, in pixels.
+    pub pane_width: i32,
+
+    /// Name of the page shown in `Home`.
+    pub tab: Option<String>,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Self {
+            width: 1024,
+            height: 768,
+            maximized: false,
+            pane_width: 360,
+            tab: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Settings {
+    pub inbox: Inbox,
+    pub content: Content,
+    pub blocklist: Blocklist,
+    pub cache: Cache,
+    pub notifications: Notifications,
+    pub network: Network,
+    pub secrets: Secrets,
+    pub window: Window,
+}
+
+impl Settings {
+    /// Settings from before there was a settings file, which were kept in a
+    /// file each.
+    fn migrate(dir: &Path) -> Self {
+        let read = |name: &str| {
+            std::fs::read_to_string(dir.join(name))
+                .map(|t| t.trim().to_owned())
+                .ok()
+        };
+
+        let mut settings = Self::default();
+        let notifications = &mut settings.notifications;
+
+        if let Some(minutes) = read("poll-interval") {
+            notifications.poll_interval = minutes.parse().unwrap_or(0);
+        }
+
+        if let Some(enabled) = read("run-in-background") {
+            notifications.run_in_background = enabled == "true";
+        }
+
+        if let Some(backend) = read("secrets-backend") {
+            settings.secrets.backend =
+                Backend::from_str(&backend).unwrap_or_default();
+        }
+
+        settings
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    path: PathBuf,
+    sender: watch::Sender<Settings>,
+
+    /// Kept so sending never fails for lack of subscribers.
+    receiver: watch::Receiver<Settings>,
+}
+
+/// Shared handle to the current settings.
+#[derive(Debug, Clone)]
+pub struct Store(Rc<Inner>);
+
+impl Store {
+    /// Read the settings, falling back to the defaults if they can't be.
+    ///
+    /// A file that can't be read is left alone rather than saved over.
+    pub fn load() -> Self {
+        let (path, settings) = match Self::read() {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("{}", e);
+                (PathBuf::new(), Settings::default())
+            }
+        };
+
+        let (sender, receiver) = watch::channel(settings);
+
+        Self(Rc::new(Inner {
+            path,
+            sender,
+            receiver,
+        }))
+    }
+
+    fn read() -> Result<(PathBuf, Settings), Error> {
+        let dirs = ProjectDirs::from(
+            crate::QUALIFIER,
+            crate::ORGANIZATION,
+            env!("CARGO_PKG_NAME"),
+        )
+        .context(Directories)?;
+
+        let dir = dirs.config_dir();
+        let path = dir.join("settings.toml");
+
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                toml::from_str(&text).with_context(|| Parse { path: &path })?
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Settings::migrate(dir)
+            }
+            Err(e) => return Err(e).context(Read { path }),
+        };
+
+        Ok((path, settings))
+    }
+
+    pub fn get(&self) -> Settings {
+        self.0.receiver.borrow().clone()
+    }
+
+    /// A receiver that's told about changes, starting with the current
+    /// settings.
+    pub fn subscribe(&self) -> watch::Receiver<Settings> {
+        self.0.receiver.clone()
+    }
+
+    /// Change the settings with `func` and save them.
+    ///
+    /// Subscribers hear about the change even if saving fails, so it lasts
+    /// at least until the app exits.
+    pub fn update<F>(&self, func: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Settings),
+    {
+        let mut settings = self.get();
+        func(&mut settings);
+
+        if settings == self.get() {
+            return Ok(());
+        }
+
+        self.0.sender.send(settings.clone()).ok();
+        self.save(&settings)
+    }
+
+    fn save(&self, settings: &Settings) -> Result<(), Error> {
+        let path = &self.0.path;
+
+        // The directories couldn't be found when loading.
+        if path.as_os_str().is_empty() {
+            return Ok(());
+        }
+
+        let text = toml::to_string(settings).context(Serialize)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context(Write { path })?;
+        }
+
+        std::fs::write(path, text).context(Write { path })
+    }
+}
@@ -1,3 +1,14 @@
+pub mod accounts;
+pub mod background;
+pub mod gallery;
 pub mod home;
+pub mod inbox;
+pub mod journal;
+pub mod login;
+pub mod navigation;
+pub mod preferences;
 pub mod root;
+pub mod search;
 pub mod submissions;
+pub mod user;
+pub mod view;
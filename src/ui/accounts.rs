@@ -0,0 +1,95 @@
+use gettextrs::gettext;
+
+use glib::ToVariant;
+
+use gtk::prelude::*;
+
+use super::root::Root;
+
+/// Header bar button listing the stored accounts.
+///
+/// Rows activate `win.switch-account` and `win.add-account`, which `Root`
+/// handles.
+#[derive(Debug)]
+pub struct AccountSwitcher {
+    button: gtk::MenuButton,
+    rows: gtk::Box,
+}
+
+impl AccountSwitcher {
+    pub(crate) fn new() -> Self {
+        let rows = gtk::Box::new(gtk::Orientation::Vertical, 0);
+
+        let add = gtk::ButtonBuilder::new()
+            .label(&gettext("Add Account…"))
+            .action_name(Root::ADD_ACCOUNT)
+            .build();
+        add.add_css_class("flat");
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        content.append(&rows);
+        content.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+        content.append(&add);
+
+        let popover = gtk::PopoverBuilder::new().child(&content).build();
+
+        let button = gtk::MenuButtonBuilder::new()
+            .icon_name("system-users-symbolic")
+            .tooltip_text(&gettext("Switch Account"))
+            .popover(&popover)
+            .build();
+
+        Self { button, rows }
+    }
+
+    pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.button
+    }
+
+    pub(crate) fn set_accounts(
+        &self,
+        accounts: &[String],
+        active: Option<&str>,
+    ) {
+        while let Some(child) = self.rows.first_child() {
+            self.rows.remove(&child);
+        }
+
+        for account in accounts {
+            let label = gtk::LabelBuilder::new()
+                .label(account)
+                .xalign(0.)
+                .hexpand(true)
+                .build();
+
+            // Keep the check's space for every row, so labels line up.
+            let check = gtk::ImageBuilder::new()
+                .icon_name("object-select-symbolic")
+                .build();
+            check.set_opacity(0.);
+            if Some(account.as_str()) == active {
+                check.set_opacity(1.);
+            }
+
+            let box_ = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+            box_.append(&label);
+            box_.append(&check);
+
+            let row = gtk::ButtonBuilder::new()
+                .child(&box_)
+                .action_name(Root::SWITCH_ACCOUNT)
+                .action_target(&account.to_variant())
+                .build();
+            row.add_css_class("flat");
+
+            self.rows.append(&row);
+        }
+
+        let tooltip = match active {
+            Some(a) => format!("{} ({})", gettext("Switch Account"), a),
+            None => gettext("Switch Account"),
+        };
+
+        self.button.set_tooltip_text(Some(&tooltip));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::ptr::{Owned, Wrap};
+use crate::secrets::{Secrets, SecretsExt};
+use crate::util::{Tasks, Util};
+
+use gettextrs::gettext;
+
+use gio::prelude::*;
+
+use glib::{ToVariant, Variant};
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+/// Keeps the app, and with it the bridge and inbox checks, running after
+/// the window is closed.
+///
+/// Also how the app stays up when started with `--gapplication-service`,
+/// which never opens a window on its own.
+#[derive(Debug)]
+pub struct Background {
+    application: gtk::Application,
+    util: Util,
+
+    enabled: Cell<bool>,
+    held: Cell<bool>,
+
+    tasks: Tasks,
+}
+
+impl Background {
+    pub const RUN_IN_BACKGROUND: &'static str = "app.run-in-background";
+    pub const QUIT: &'static str = "app.quit";
+
+    pub(crate) fn new(
+        application: &gtk::Application,
+        util: Util,
+    ) -> Owned<Self> {
+        let enabled = util.settings().get().notifications.run_in_background;
+
+        let background_action = gio::SimpleAction::new_stateful(
+            "run-in-background",
+            None,
+            &enabled.to_variant(),
+        );
+        let quit_action = gio::SimpleAction::new("quit", None);
+
+        application.add_action(&background_action);
+        application.add_action(&quit_action);
+        application.set_accels_for_action(Self::QUIT, &["<Primary>q"]);
+
+        let owned = Owned::new(Self {
+            application: application.clone(),
+            util,
+            enabled: Cell::new(false),
+            held: Cell::new(false),
+            tasks: Tasks::default(),
+        });
+
+        let settings = owned.util.settings().clone();
+        background_action.connect_change_state(move |_, value| {
+            if let Some(enabled) = value.and_then(|v| v.get::<bool>()) {
+                let result = settings
+                    .update(|s| s.notifications.run_in_background = enabled);
+
+                if let Err(e) = result {
+                    log::warn!("unable to store background setting: {}", e);
+                }
+            }
+        });
+
+        let app = application.clone();
+        quit_action.connect_activate(move |_, _| app.quit());
+
+        // The portal can't be asked until the app is on the bus.
+        let weak = Owned::downgrade(&owned);
+        application.connect_startup(move |app| {
+            if let Some(this) = weak.upgrade() {
+                this.apply(enabled);
+                this.watch_settings(background_action.clone());
+
+                // Without a window, nothing else logs the bridge in.
+                if app.flags().contains(gio::ApplicationFlags::IS_SERVICE) {
+                    this.log_in();
+                }
+            }
+        });
+
+        owned
+    }
+
+    /// Whether the app should stay up once its window is closed.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+}
+
+impl Wrap<Background> {
+    fn watch_settings(&self, action: gio::SimpleAction) {
+        let mut settings = self.util.settings().subscribe();
+        let weak = self.weak();
+
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            while settings.changed().await.is_ok() {
+                let this = match weak.upgrade() {
+                    Some(t) => t,
+                    None => break,
+                };
+
+                let enabled = settings.borrow().notifications.run_in_background;
+                if this.enabled.get() != enabled {
+                    action.set_state(&enabled.to_variant());
+                    this.apply(enabled);
+                }
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    fn apply(&self, enabled: bool) {
+        self.enabled.set(enabled);
+
+        if enabled && !self.held.replace(true) {
+            self.application.hold();
+            self.request_portal();
+        } else if !enabled && self.held.replace(false) {
+            self.application.release();
+        }
+    }
+
+    /// Log the bridge in as the last used account, for running as a service.
+    fn log_in(&self) {
+        let account = match self.util.account() {
+            Some(a) => a,
+            None => return,
+        };
+
+        let util = self.util.clone();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let name = account.clone();
+            let cookie = Secrets::with(&util, move |s| {
+                // Asking for a passphrase needs a window.
+                if s.needs_unlock() {
+                    return Ok(None);
+                }
+
+                s.get(&name)
+            })
+            .await;
+
+            let cookie = match cookie {
+                Ok(Some(c)) => c,
+                Ok(None) => {
+                    log::info!("no usable cookies for {}", account);
+                    return Ok(());
+                }
+                Err(e) => {
+                    log::warn!("unable to read cookies: {}", e);
+                    return Ok(());
+                }
+            };
+
+            if let Err(e) = util.client().login(&cookie).await {
+                log::warn!("unable to log in as {}: {}", account, e);
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Ask the background portal for permission to keep running, so
+    /// sandboxed instances aren't killed once the window is closed.
+    fn request_portal(&self) {
+        let connection = match self.application.dbus_connection() {
+            Some(c) => c,
+            None => return,
+        };
+
+        let mut options: HashMap<String, Variant> = HashMap::new();
+        options.insert(
+            "reason".into(),
+            gettext("Check for new submissions and messages").to_variant(),
+        );
+        options.insert("autostart".into(), false.to_variant());
+
+        let parameters = (String::new(), options).to_variant();
+
+        connection.call(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Background",
+            "RequestBackground",
+            Some(&parameters),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::NONE_CANCELLABLE,
+            |result| {
+                // Outside a sandbox there may be no portal, and nothing
+                // will kill the app anyway.
+                if let Err(e) = result {
+                    log::info!("background portal unavailable: {}", e);
+                }
+            },
+        );
+    }
+}
+
+impl Drop for Background {
+    fn drop(&mut self) {
+        if self.held.get() {
+            self.application.release();
+        }
+    }
+}
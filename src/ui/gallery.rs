@@ -0,0 +1,232 @@
+use crate::ptr::{Owned, Wrap};
+use crate::settings::{ThumbnailSize, Visibility};
+use crate::util::{Tasks, Util};
+use crate::widgets::FillImage;
+
+use gettextrs::gettext;
+
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use labrat::keys::{GalleryKey, ViewKey};
+use labrat::resources::Submission;
+
+type OpenFn = Rc<dyn Fn(ViewKey)>;
+
+/// A user's gallery, opened from a link, a page of submissions at a time.
+#[derive(Debug)]
+pub struct GalleryPage {
+    util: Util,
+    tasks: Tasks,
+
+    /// Whose gallery it is, once fetched.
+    loaded: RefCell<Option<String>>,
+
+    /// The gallery's next page, if it has one.
+    next: RefCell<Option<GalleryKey>>,
+
+    scrolled_window: gtk::ScrolledWindow,
+    title: gtk::Label,
+    flow: gtk::FlowBox,
+    more_btn: gtk::Button,
+}
+
+impl GalleryPage {
+    /// Create the page and start fetching it, calling `on_loaded` once
+    /// there's a title to show, and `on_open` with each submission that's
+    /// clicked.
+    pub(crate) fn new<F, O>(
+        util: Util,
+        key: GalleryKey,
+        on_loaded: F,
+        on_open: O,
+    ) -> Owned<Self>
+    where
+        F: 'static + Fn(),
+        O: 'static + Fn(ViewKey),
+    {
+        let title = gtk::LabelBuilder::new()
+            .label(&gettext("Loading…"))
+            .wrap(true)
+            .xalign(0.)
+            .build();
+        title.add_css_class("title-1");
+
+        let flow = gtk::FlowBoxBuilder::new()
+            .selection_mode(gtk::SelectionMode::None)
+            .homogeneous(true)
+            .column_spacing(6)
+            .row_spacing(6)
+            .build();
+
+        let more_btn = gtk::ButtonBuilder::new()
+            .label(&gettext("Load More"))
+            .halign(gtk::Align::Center)
+            .visible(false)
+            .build();
+
+        let box_ = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .margin_top(18)
+            .margin_bottom(18)
+            .margin_start(18)
+            .margin_end(18)
+            .build();
+
+        box_.append(&title);
+        box_.append(&flow);
+        box_.append(&more_btn);
+
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_child(Some(&box_));
+
+        let owned = Owned::new(Self {
+            util,
+            tasks: Tasks::default(),
+            loaded: Default::default(),
+            next: Default::default(),
+            scrolled_window,
+            title,
+            flow,
+            more_btn,
+        });
+
+        let on_open: OpenFn = Rc::new(on_open);
+
+        let weak = Owned::downgrade(&owned);
+        let on_open_clone = on_open.clone();
+        owned.more_btn.connect_clicked(move |btn| {
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return,
+            };
+
+            if let Some(key) = this.next.borrow_mut().take() {
+                btn.set_sensitive(false);
+                this.fetch(key, || (), on_open_clone.clone());
+            }
+        });
+
+        owned.fetch(key, on_loaded, on_open);
+
+        owned
+    }
+}
+
+impl Wrap<GalleryPage> {
+    pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.scrolled_window
+    }
+
+    /// The gallery's title, once it has loaded.
+    pub(crate) fn title(&self) -> Option<String> {
+        let artist = self.loaded.borrow().clone()?;
+        Some(gettext("Gallery of {}").replacen("{}", &artist, 1))
+    }
+
+    pub(crate) fn artist(&self) -> Option<String> {
+        self.loaded.borrow().clone()
+    }
+
+    fn fetch<F>(&self, key: GalleryKey, on_loaded: F, on_open: OpenFn)
+    where
+        F: 'static + Fn(),
+    {
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let client = match weak.upgrade() {
+                Some(t) => t.util.client().clone(),
+                None => return Ok(()),
+            };
+
+            let result = client.gallery(key).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            this.more_btn.set_sensitive(true);
+
+            let gallery = match result {
+                Ok(g) => g,
+                Err(e) => {
+                    this.title.set_text(&format!(
+                        "{}: {}",
+                        gettext("Unable to load gallery"),
+                        e
+                    ));
+                    return Ok(());
+                }
+            };
+
+            let next = gallery.next().cloned();
+            this.more_btn.set_visible(next.is_some());
+            this.next.replace(next);
+
+            let submissions = gallery.into_items();
+
+            if this.loaded.borrow().is_none() {
+                // Everything in a gallery is by the same artist.
+                let artist = submissions
+                    .first()
+                    .map(|s| s.artist().name().to_owned())
+                    .unwrap_or_default();
+
+                this.loaded.replace(Some(artist));
+                this.title.set_text(&this.title().unwrap_or_default());
+                on_loaded();
+            }
+
+            for submission in submissions.iter() {
+                this.add_submission(submission, &on_open);
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Add a thumbnail for `submission`, unless its rating hides it.
+    fn add_submission(&self, submission: &Submission, on_open: &OpenFn) {
+        let content = self.util.settings().get().content;
+        let visibility = content.visibility(submission.rating());
+        if visibility == Visibility::Hide {
+            return;
+        }
+
+        let image = FillImage::new();
+        image.set_size_request(160, 160);
+        image.set_blurred(visibility == Visibility::Blur);
+
+        let button = gtk::ButtonBuilder::new()
+            .child(&image)
+            .tooltip_text(submission.title())
+            .build();
+        button.add_css_class("flat");
+
+        let key = ViewKey::from(submission);
+        let on_open = on_open.clone();
+        button.connect_clicked(move |_| on_open(key.clone()));
+
+        self.flow.insert(&button, -1);
+
+        let thumb = submission.preview(ThumbnailSize::Medium.preview_size());
+        let util = self.util.clone();
+        let task = self.util.spawn_local(async move {
+            match util.fetch_pixbuf(thumb.as_str()).await {
+                Ok(pixbuf) => image.set_pixbuf(pixbuf),
+                Err(e) => log::warn!("unable to load {}: {}", thumb, e),
+            }
+
+            Result::<_, Infallible>::Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+}
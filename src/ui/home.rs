@@ -9,6 +9,7 @@ use gtk::prelude::*;
 
 use std::cell::RefCell;
 
+use super::navigation::Route;
 use super::root::Root;
 use super::submissions::Submissions;
 
@@ -17,7 +18,9 @@ pub struct Home {
     submissions: Owned<Submissions>,
     util: Util,
 
-    notebook: gtk::Notebook,
+    stack: gtk::Stack,
+    switcher_bar: adw::ViewSwitcherBar,
+    box_: gtk::Box,
 }
 
 impl Home {
@@ -25,23 +28,67 @@ impl Home {
         let submissions = Submissions::new(util.clone());
         submissions.fetch();
 
-        let notebook = gtk::NotebookBuilder::new().build();
+        let stack = gtk::StackBuilder::new().vexpand(true).build();
 
-        notebook.append_page(
+        let page = stack.add_titled(
             submissions.widget(),
-            Some(&gtk::Label::new(Some(&gettext("Submissions")))),
+            Some("submissions"),
+            &gettext("Submissions"),
         );
+        page.set_icon_name(Some("image-x-generic-symbolic"));
+
+        // Only revealed when the switcher doesn't fit in the header bar.
+        let switcher_bar = adw::ViewSwitcherBar::new();
+        switcher_bar.set_stack(Some(&stack));
+
+        let box_ = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .width_request(360)
+            .build();
+
+        box_.append(&stack);
+        box_.append(&switcher_bar);
 
         Owned::new(Self {
             util,
             submissions,
-            notebook,
+            stack,
+            switcher_bar,
+            box_,
         })
     }
 }
 
 impl Wrap<Home> {
     pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
-        &self.notebook
+        &self.box_
+    }
+
+    pub(crate) fn stack(&self) -> &gtk::Stack {
+        &self.stack
+    }
+
+    /// Name of the page being shown, like `submissions`.
+    pub(crate) fn tab(&self) -> Option<String> {
+        self.stack.visible_child_name().map(|n| n.to_string())
+    }
+
+    pub(crate) fn set_tab(&self, name: &str) {
+        if self.stack.child_by_name(name).is_some() {
+            self.stack.set_visible_child_name(name);
+        }
+    }
+
+    pub(crate) fn reveal_switcher(&self, reveal: bool) {
+        self.switcher_bar.set_reveal(reveal);
+    }
+
+    /// Call `func` with the route of whatever is opened from the inbox.
+    pub(crate) fn connect_open<F>(&self, func: F)
+    where
+        F: 'static + Fn(Route),
+    {
+        self.submissions
+            .connect_open(move |key| func(Route::View(key)));
     }
 }
@@ -0,0 +1,567 @@
+use crate::ptr::{Owned, Weak, Wrap};
+use crate::util::{Tasks, Util};
+
+use gettextrs::{gettext, ngettext};
+
+use gio::prelude::*;
+
+use glib::{StaticVariantType, ToVariant};
+
+use labrat::keys::{SubmissionsKey, ViewKey};
+use labrat::resources::msg::others::Others;
+use labrat::resources::Submission;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+
+use super::navigation::Route;
+use super::root::Root;
+
+/// Kinds of inbox items, each getting its own notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Submissions,
+    Comments,
+    Journals,
+    Favorites,
+    Watches,
+    Shouts,
+}
+
+impl Kind {
+    const ALL: &'static [Kind] = &[
+        Kind::Submissions,
+        Kind::Comments,
+        Kind::Journals,
+        Kind::Favorites,
+        Kind::Watches,
+        Kind::Shouts,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Submissions => "submissions",
+            Kind::Comments => "comments",
+            Kind::Journals => "journals",
+            Kind::Favorites => "favorites",
+            Kind::Watches => "watches",
+            Kind::Shouts => "shouts",
+        }
+    }
+
+    fn from_str(text: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|k| k.as_str() == text)
+    }
+
+    /// Id of the notification for this kind, so newer ones replace it.
+    fn notification_id(self) -> String {
+        format!("inbox-{}", self.as_str())
+    }
+
+    fn title(self, count: usize) -> String {
+        let n = count as u32;
+        let text = match self {
+            Kind::Submissions => {
+                ngettext("{} new submission", "{} new submissions", n)
+            }
+            Kind::Comments => ngettext("{} new comment", "{} new comments", n),
+            Kind::Journals => ngettext("{} new journal", "{} new journals", n),
+            Kind::Favorites => {
+                ngettext("{} new favorite", "{} new favorites", n)
+            }
+            Kind::Watches => ngettext("{} new watcher", "{} new watchers", n),
+            Kind::Shouts => ngettext("{} new shout", "{} new shouts", n),
+        };
+
+        text.replacen("{}", &count.to_string(), 1)
+    }
+}
+
+/// Something in the inbox.
+#[derive(Debug)]
+struct Item {
+    /// Tells whether it's been seen. Ids come from the site, so an item keeps
+    /// its id from one check to the next however else its listing changes.
+    id: String,
+
+    /// Describes it for the notification, if there's anything to say.
+    line: Option<String>,
+
+    /// Where opening it goes, if it has a page of its own.
+    route: Option<Route>,
+}
+
+fn submission_id(key: &ViewKey) -> String {
+    key.view_id.to_string()
+}
+
+fn submission_item(submission: &Submission) -> Item {
+    let line = gettext("{title} by {artist}")
+        .replace("{title}", submission.title())
+        .replace("{artist}", submission.artist().name());
+
+    let key = ViewKey::from(submission);
+
+    Item {
+        id: submission_id(&key),
+        line: Some(line),
+        route: Some(Route::View(key)),
+    }
+}
+
+/// Items on the others page, by kind.
+fn others_items(others: &Others) -> Vec<(Kind, Vec<Item>)> {
+    fn items<T, I>(found: &[T], id: impl Fn(&T) -> I) -> Vec<Item>
+    where
+        I: ToString,
+    {
+        found
+            .iter()
+            .map(|i| Item {
+                id: id(i).to_string(),
+                line: None,
+                route: None,
+            })
+            .collect()
+    }
+
+    vec![
+        (Kind::Comments, items(others.comments(), |c| c.id())),
+        (Kind::Journals, items(others.journals(), |j| j.id())),
+        (Kind::Favorites, items(others.favorites(), |f| f.id())),
+        (Kind::Watches, items(others.watches(), |w| w.id())),
+        (Kind::Shouts, items(others.shouts(), |s| s.id())),
+    ]
+}
+
+#[derive(Debug, Default)]
+struct Unread {
+    lines: Vec<String>,
+    count: usize,
+
+    /// Where to go when the notification is clicked, if there's only one
+    /// item to open.
+    route: Option<Route>,
+}
+
+/// Checks the inbox in the background and sends notifications for anything
+/// new, keeping the app running while it's enabled.
+///
+/// Whatever is in the inbox on the first check for an account counts as
+/// seen, so starting the app doesn't notify about old items.
+#[derive(Debug)]
+pub struct Inbox {
+    application: gtk::Application,
+    util: Util,
+
+    /// Minutes between checks, or zero to not check.
+    interval: Cell<u32>,
+    held: Cell<bool>,
+    /// Whether a check is waiting on the bridge.
+    polling: Cell<bool>,
+
+    /// The window, once it's open, for going to whatever is clicked.
+    root: RefCell<Weak<Root>>,
+
+    account: RefCell<Option<String>>,
+    /// Ids in the inbox as of the last check.
+    seen: RefCell<HashMap<Kind, HashSet<String>>>,
+    unread: RefCell<HashMap<Kind, Unread>>,
+    unread_submissions: RefCell<Vec<ViewKey>>,
+
+    timer: Tasks,
+    tasks: Tasks,
+}
+
+impl Inbox {
+    pub const POLL_INTERVAL: &'static str = "app.poll-interval";
+    const OPEN: &'static str = "app.open-inbox";
+    const MARK_READ: &'static str = "app.mark-inbox-read";
+
+    /// Intervals offered in the menu, in minutes.
+    pub const INTERVALS: &'static [u32] = &[0, 5, 15, 30, 60];
+
+    pub(crate) fn new(
+        application: &gtk::Application,
+        util: Util,
+    ) -> Owned<Self> {
+        let interval = util.settings().get().notifications.poll_interval;
+
+        let interval_action = gio::SimpleAction::new_stateful(
+            "poll-interval",
+            Some(&u32::static_variant_type()),
+            &interval.to_variant(),
+        );
+        let open_action = gio::SimpleAction::new(
+            "open-inbox",
+            Some(&String::static_variant_type()),
+        );
+        let mark_read_action = gio::SimpleAction::new(
+            "mark-inbox-read",
+            Some(&String::static_variant_type()),
+        );
+
+        application.add_action(&interval_action);
+        application.add_action(&open_action);
+        application.add_action(&mark_read_action);
+
+        let owned = Owned::new(Self {
+            application: application.clone(),
+            util,
+            interval: Cell::new(interval),
+            held: Cell::new(false),
+            polling: Cell::new(false),
+            root: RefCell::new(Weak::new()),
+            account: Default::default(),
+            seen: Default::default(),
+            unread: Default::default(),
+            unread_submissions: Default::default(),
+            timer: Tasks::default(),
+            tasks: Tasks::default(),
+        });
+
+        // Applied once the settings say so, along with changes made in the
+        // preferences.
+        let settings = owned.util.settings().clone();
+        interval_action.connect_change_state(move |_, value| {
+            if let Some(minutes) = value.and_then(|v| v.get::<u32>()) {
+                let result = settings
+                    .update(|s| s.notifications.poll_interval = minutes);
+
+                if let Err(e) = result {
+                    log::warn!("unable to store inbox interval: {}", e);
+                }
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        open_action.connect_activate(move |_, param| {
+            let kind = param
+                .and_then(|p| p.get::<String>())
+                .and_then(|p| Kind::from_str(&p));
+
+            if let (Some(this), Some(kind)) = (weak.upgrade(), kind) {
+                this.open(kind);
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        mark_read_action.connect_activate(move |_, param| {
+            let kind = param
+                .and_then(|p| p.get::<String>())
+                .and_then(|p| Kind::from_str(&p));
+
+            if let (Some(this), Some(kind)) = (weak.upgrade(), kind) {
+                this.mark_read(kind);
+            }
+        });
+
+        owned.schedule();
+        owned.watch_settings(interval_action);
+
+        owned
+    }
+
+    /// How to describe checking every `minutes`.
+    pub fn interval_label(minutes: u32) -> String {
+        match minutes {
+            0 => gettext("Never"),
+            60 => gettext("Every Hour"),
+            m => gettext("Every {} Minutes").replacen("{}", &m.to_string(), 1),
+        }
+    }
+
+    /// Whether the inbox is being checked, which needs the app to keep
+    /// running.
+    pub fn is_checking(&self) -> bool {
+        self.interval.get() > 0
+    }
+}
+
+impl Wrap<Inbox> {
+    /// Open clicked notifications in `root`.
+    pub(crate) fn set_root(&self, root: Weak<Root>) {
+        self.root.replace(root);
+    }
+
+    fn watch_settings(&self, interval_action: gio::SimpleAction) {
+        let mut settings = self.util.settings().subscribe();
+        let weak = self.weak();
+
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            while settings.changed().await.is_ok() {
+                let this = match weak.upgrade() {
+                    Some(t) => t,
+                    None => break,
+                };
+
+                let minutes = settings.borrow().notifications.poll_interval;
+                if this.interval.replace(minutes) != minutes {
+                    interval_action.set_state(&minutes.to_variant());
+                    this.schedule();
+                }
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Restart the timer for the current interval.
+    fn schedule(&self) {
+        self.timer.cancel_all();
+
+        let minutes = self.interval.get();
+
+        // Keep running after the window is closed, so checks continue.
+        if minutes > 0 && !self.held.replace(true) {
+            self.application.hold();
+        } else if minutes == 0 && self.held.replace(false) {
+            self.application.release();
+        }
+
+        if minutes == 0 {
+            return;
+        }
+
+        // Check right away, so whatever is already in the inbox counts as
+        // seen before the first interval is up.
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            loop {
+                match weak.upgrade() {
+                    Some(this) => this.poll(),
+                    None => return Ok(()),
+                }
+
+                glib::timeout_future_seconds(minutes * 60).await;
+            }
+        });
+
+        self.timer.push(task);
+    }
+
+    fn poll(&self) {
+        let account = match self.util.account() {
+            Some(a) => a,
+            None => return,
+        };
+
+        // Background requests can wait behind the user's for longer than an
+        // interval, and checking twice at once would notify twice.
+        if self.polling.replace(true) {
+            log::debug!("skipping inbox check, the last one isn't done");
+            return;
+        }
+
+        // Queued behind anything the user is waiting on, and subject to the
+        // bridge's rate limit like every other request.
+        let client = self.util.client().background();
+
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let submissions =
+                client.submissions(SubmissionsKey::newest()).await;
+            let others = client.others().await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            this.polling.set(false);
+
+            // Results for an account that's no longer active are stale.
+            if this.util.account().as_deref() != Some(account.as_str()) {
+                return Ok(());
+            }
+
+            if this.account.borrow().as_deref() != Some(account.as_str()) {
+                this.reset();
+                this.account.replace(Some(account));
+            }
+
+            match submissions {
+                Ok(submissions) => {
+                    let submissions = submissions.into_items();
+                    let items = submissions.iter().map(submission_item);
+                    let fresh = this.update(Kind::Submissions, items.collect());
+
+                    this.unread_submissions.borrow_mut().extend(
+                        submissions
+                            .iter()
+                            .map(ViewKey::from)
+                            .filter(|k| fresh.contains(&submission_id(k))),
+                    );
+                }
+                Err(e) => log::warn!("unable to check submissions: {}", e),
+            }
+
+            match others {
+                Ok(others) => {
+                    for (kind, items) in others_items(&others) {
+                        this.update(kind, items);
+                    }
+                }
+                Err(e) => log::warn!("unable to check inbox: {}", e),
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Forget everything seen, for example after switching accounts.
+    fn reset(&self) {
+        for kind in Kind::ALL {
+            self.application
+                .withdraw_notification(&kind.notification_id());
+        }
+
+        self.seen.borrow_mut().clear();
+        self.unread.borrow_mut().clear();
+        self.unread_submissions.borrow_mut().clear();
+    }
+
+    /// Record `items` as seen, notifying about the ones that weren't, and
+    /// returning their ids.
+    fn update(&self, kind: Kind, items: Vec<Item>) -> Vec<String> {
+        let fresh: Vec<_> = {
+            let mut seen = self.seen.borrow_mut();
+            let ids: HashSet<_> = items.iter().map(|i| i.id.clone()).collect();
+
+            // An empty first check may only mean the bridge wasn't logged in
+            // yet, so it doesn't count as having seen the inbox.
+            if ids.is_empty() && !seen.contains_key(&kind) {
+                return Vec::new();
+            }
+
+            // Anything no longer in the inbox won't come back, so only the
+            // latest ids are kept.
+            let previous = match seen.insert(kind, ids) {
+                Some(p) => p,
+                None => return Vec::new(),
+            };
+
+            items
+                .into_iter()
+                .filter(|i| !previous.contains(&i.id))
+                .collect()
+        };
+
+        if fresh.is_empty() {
+            return Vec::new();
+        }
+
+        let mut unread = self.unread.borrow_mut();
+        let unread = unread.entry(kind).or_default();
+        unread.count += fresh.len();
+        unread
+            .lines
+            .extend(fresh.iter().filter_map(|i| i.line.clone()));
+        unread.route = match unread.count {
+            1 => fresh[0].route.clone(),
+            _ => None,
+        };
+
+        let target = kind.as_str().to_variant();
+
+        let notification = gio::Notification::new(&kind.title(unread.count));
+        notification
+            .set_default_action_and_target_value(Inbox::OPEN, Some(&target));
+        notification.add_button_with_target_value(
+            &gettext("Mark Read"),
+            Inbox::MARK_READ,
+            Some(&target),
+        );
+
+        // Newest first, and only a few; the app shows the rest.
+        let lines: Vec<_> = unread
+            .lines
+            .iter()
+            .rev()
+            .take(3)
+            .map(String::as_str)
+            .collect();
+        if !lines.is_empty() {
+            notification.set_body(Some(&lines.join("\n")));
+        }
+
+        if self.util.settings().get().notifications.notify {
+            self.application.send_notification(
+                Some(&kind.notification_id()),
+                &notification,
+            );
+        }
+
+        fresh.into_iter().map(|i| i.id).collect()
+    }
+
+    fn dismiss(&self, kind: Kind) {
+        self.unread.borrow_mut().remove(&kind);
+        self.application
+            .withdraw_notification(&kind.notification_id());
+    }
+
+    /// Show what the notification for `kind` is about: the item itself if
+    /// there's only one, or the inbox otherwise.
+    fn open(&self, kind: Kind) {
+        let route = self
+            .unread
+            .borrow()
+            .get(&kind)
+            .and_then(|u| u.route.clone());
+
+        self.dismiss(kind);
+
+        if kind == Kind::Submissions {
+            self.unread_submissions.borrow_mut().clear();
+        }
+
+        // Opens the window first if it isn't already.
+        self.application.activate();
+
+        if let Some(root) = self.root.borrow().upgrade() {
+            root.navigate(route.unwrap_or(Route::Home));
+        }
+    }
+
+    /// Stop notifying about `kind`, clearing new submissions on the site too.
+    ///
+    /// The bridge can't clear the others page, so those are only marked as
+    /// read here.
+    fn mark_read(&self, kind: Kind) {
+        self.dismiss(kind);
+
+        if kind != Kind::Submissions {
+            return;
+        }
+
+        let keys = self.unread_submissions.replace(Vec::new());
+        if keys.is_empty() {
+            return;
+        }
+
+        let client = self.util.client().clone();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            if let Err(e) = client.clear_submissions(keys).await {
+                log::warn!("unable to clear submissions: {}", e);
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+}
+
+impl Drop for Inbox {
+    fn drop(&mut self) {
+        if self.held.get() {
+            self.application.release();
+        }
+    }
+}
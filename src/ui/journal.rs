@@ -0,0 +1,143 @@
+use crate::bridge::errors::RequestError;
+use crate::ptr::{Owned, Wrap};
+use crate::util::{Tasks, Util};
+
+use gettextrs::gettext;
+
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+
+use labrat::keys::JournalKey;
+
+/// A single journal, opened from a link.
+#[derive(Debug)]
+pub struct JournalPage {
+    util: Util,
+    tasks: Tasks,
+
+    /// Title and author, once fetched.
+    loaded: RefCell<Option<(String, String)>>,
+
+    scrolled_window: gtk::ScrolledWindow,
+    title: gtk::Label,
+    author: gtk::Label,
+    content: gtk::Label,
+}
+
+impl JournalPage {
+    /// Create the page and start fetching it, calling `on_loaded` once
+    /// there's a title to show.
+    pub(crate) fn new<F>(
+        util: Util,
+        key: JournalKey,
+        on_loaded: F,
+    ) -> Owned<Self>
+    where
+        F: 'static + Fn(),
+    {
+        let title = gtk::LabelBuilder::new()
+            .label(&gettext("Loading…"))
+            .wrap(true)
+            .xalign(0.)
+            .build();
+        title.add_css_class("title-1");
+
+        let author = gtk::LabelBuilder::new().xalign(0.).build();
+        author.add_css_class("dim-label");
+
+        let content = gtk::LabelBuilder::new()
+            .wrap(true)
+            .selectable(true)
+            .xalign(0.)
+            .yalign(0.)
+            .build();
+
+        let box_ = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .margin_top(18)
+            .margin_bottom(18)
+            .margin_start(18)
+            .margin_end(18)
+            .build();
+
+        box_.append(&title);
+        box_.append(&author);
+        box_.append(&content);
+
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_child(Some(&box_));
+
+        let owned = Owned::new(Self {
+            util,
+            tasks: Tasks::default(),
+            loaded: Default::default(),
+            scrolled_window,
+            title,
+            author,
+            content,
+        });
+
+        owned.fetch(key, on_loaded);
+
+        owned
+    }
+}
+
+impl Wrap<JournalPage> {
+    pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.scrolled_window
+    }
+
+    /// The journal's title, once it has loaded.
+    pub(crate) fn title(&self) -> Option<String> {
+        self.loaded.borrow().as_ref().map(|(t, _)| t.clone())
+    }
+
+    pub(crate) fn author(&self) -> Option<String> {
+        self.loaded.borrow().as_ref().map(|(_, a)| a.clone())
+    }
+
+    fn fetch<F>(&self, key: JournalKey, on_loaded: F)
+    where
+        F: 'static + Fn(),
+    {
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, RequestError>(async move {
+            let client = match weak.upgrade() {
+                Some(t) => t.util.client().clone(),
+                None => return Ok(()),
+            };
+
+            let result = client.journal(key).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            match result {
+                Ok(journal) => {
+                    this.title.set_text(journal.title());
+                    this.loaded.replace(Some((
+                        journal.title().to_owned(),
+                        journal.author().name().to_owned(),
+                    )));
+                    on_loaded();
+                    this.author.set_text(journal.author().name());
+                    this.content.set_text(journal.content());
+                }
+                Err(e) => this.title.set_text(&format!(
+                    "{}: {}",
+                    gettext("Unable to load journal"),
+                    e
+                )),
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+}
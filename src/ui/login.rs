@@ -0,0 +1,297 @@
+use crate::bridge::errors::LoginError;
+use crate::browser::{self, Profile};
+use crate::ptr::{Owned, Wrap};
+use crate::secrets::{Secrets, SecretsExt};
+use crate::util::{Tasks, Util};
+
+use gettextrs::gettext;
+
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Form asking for the cookies of a logged in browser session.
+#[derive(Debug)]
+pub struct Login {
+    util: Util,
+    tasks: Tasks,
+
+    box_: gtk::Box,
+    account: gtk::Entry,
+    entry: gtk::PasswordEntry,
+    button: gtk::Button,
+    error: gtk::Label,
+
+    profiles: RefCell<Vec<Profile>>,
+    profile_combo: gtk::ComboBoxText,
+    import_btn: gtk::Button,
+}
+
+impl Login {
+    /// Create the form, with the account name filled in from `account`.
+    ///
+    /// `on_success` is called with the account name once the bridge is using
+    /// the new cookies and they've been saved.
+    pub(crate) fn new<F>(
+        util: Util,
+        account: Option<&str>,
+        on_success: F,
+    ) -> Owned<Self>
+    where
+        F: 'static + Fn(&str),
+    {
+        let on_success: Rc<dyn Fn(&str)> = Rc::new(on_success);
+
+        let account = gtk::EntryBuilder::new()
+            .placeholder_text(&gettext("Account name"))
+            .text(account.unwrap_or_default())
+            .build();
+
+        let description = gtk::LabelBuilder::new()
+            .label(&gettext(
+                "Paste the cookie header from a browser logged in to \
+                 FurAffinity (for example “a=…; b=…”).",
+            ))
+            .wrap(true)
+            .xalign(0.)
+            .build();
+
+        let entry = gtk::PasswordEntryBuilder::new()
+            .placeholder_text(&gettext("Cookies"))
+            .show_peek_icon(true)
+            .activates_default(true)
+            .build();
+
+        let error = gtk::LabelBuilder::new()
+            .wrap(true)
+            .xalign(0.)
+            .visible(false)
+            .build();
+        error.add_css_class("error");
+
+        let profile_combo = gtk::ComboBoxTextBuilder::new()
+            .hexpand(true)
+            .sensitive(false)
+            .build();
+
+        let import_btn = gtk::ButtonBuilder::new()
+            .label(&gettext("Import"))
+            .tooltip_text(&gettext("Copy cookies from a browser profile"))
+            .sensitive(false)
+            .build();
+
+        let import = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        import.append(&profile_combo);
+        import.append(&import_btn);
+
+        let button = gtk::ButtonBuilder::new()
+            .label(&gettext("Log In"))
+            .halign(gtk::Align::End)
+            .build();
+        button.add_css_class("suggested-action");
+
+        let box_ = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .margin_top(18)
+            .margin_bottom(18)
+            .margin_start(18)
+            .margin_end(18)
+            .build();
+
+        box_.append(&description);
+        box_.append(&account);
+        box_.append(&import);
+        box_.append(&entry);
+        box_.append(&error);
+        box_.append(&button);
+
+        let owned = Owned::new(Self {
+            util,
+            tasks: Tasks::default(),
+            box_,
+            account,
+            entry,
+            button,
+            error,
+            profiles: Default::default(),
+            profile_combo,
+            import_btn,
+        });
+
+        let weak = Owned::downgrade(&owned);
+        owned.import_btn.connect_clicked(move |_| {
+            if let Some(this) = weak.upgrade() {
+                this.import();
+            }
+        });
+
+        owned.load_profiles();
+
+        let weak = Owned::downgrade(&owned);
+        let on_success0 = on_success.clone();
+        owned.button.connect_clicked(move |_| {
+            if let Some(this) = weak.upgrade() {
+                this.submit(on_success0.clone());
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        owned.entry.connect_activate(move |_| {
+            if let Some(this) = weak.upgrade() {
+                this.submit(on_success.clone());
+            }
+        });
+
+        owned
+    }
+}
+
+impl Wrap<Login> {
+    pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.box_
+    }
+
+    fn load_profiles(&self) {
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, browser::Error>(async move {
+            let util = match weak.upgrade() {
+                Some(t) => t.util.clone(),
+                None => return Ok(()),
+            };
+
+            let profiles = util.spawn_background(browser::profiles).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            // Not finding any browsers isn't worth complaining about.
+            let profiles = profiles.unwrap_or_default();
+
+            for profile in &profiles {
+                this.profile_combo.append_text(&profile.to_string());
+            }
+
+            if !profiles.is_empty() {
+                this.profile_combo.set_active(Some(0));
+                this.profile_combo.set_sensitive(true);
+                this.import_btn.set_sensitive(true);
+            }
+
+            this.profiles.replace(profiles);
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    fn import(&self) {
+        let profile = match self.profile_combo.active() {
+            Some(i) => self.profiles.borrow().get(i as usize).cloned(),
+            None => None,
+        };
+
+        let profile = match profile {
+            Some(p) => p,
+            None => return,
+        };
+
+        self.import_btn.set_sensitive(false);
+        self.error.hide();
+
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, browser::Error>(async move {
+            let util = match weak.upgrade() {
+                Some(t) => t.util.clone(),
+                None => return Ok(()),
+            };
+
+            let cookie = util.spawn_background(move || profile.cookie()).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            this.import_btn.set_sensitive(true);
+
+            match cookie {
+                Ok(Some(cookie)) => this.entry.set_text(&cookie),
+                Ok(None) => this.show_error(&gettext(
+                    "That profile isn't logged in to FurAffinity",
+                )),
+                Err(e) => this.show_error(&format!(
+                    "{}: {}",
+                    gettext("Unable to import cookies"),
+                    e
+                )),
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    fn show_error(&self, message: &str) {
+        self.error.set_text(message);
+        self.error.show();
+        self.button.set_sensitive(true);
+    }
+
+    fn submit(&self, on_success: Rc<dyn Fn(&str)>) {
+        let account = self.account.text().trim().to_owned();
+        if account.is_empty() {
+            self.account.grab_focus();
+            return;
+        }
+
+        let cookie = self.entry.text().to_string();
+        if cookie.trim().is_empty() {
+            return;
+        }
+
+        self.button.set_sensitive(false);
+        self.error.hide();
+
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, LoginError>(async move {
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            if let Err(e) = this.util.client().login(&cookie).await {
+                this.show_error(&format!(
+                    "{}: {}",
+                    gettext("Unable to use those cookies"),
+                    e
+                ));
+                return Ok(());
+            }
+
+            let name = account.clone();
+            let saved =
+                Secrets::with(&this.util, move |s| s.set(&name, &cookie)).await;
+            if let Err(e) = saved {
+                this.show_error(&format!(
+                    "{}: {}",
+                    gettext("Unable to save cookies"),
+                    e
+                ));
+                return Ok(());
+            }
+
+            this.button.set_sensitive(true);
+            on_success(&account);
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+}
@@ -0,0 +1,249 @@
+//! Where the window has been, and the pages it can go back to without
+//! fetching them again.
+
+use crate::link::Link;
+use crate::ptr::Owned;
+
+use gettextrs::gettext;
+
+use gio::prelude::*;
+
+use glib::ToVariant;
+
+use gtk::prelude::*;
+
+use labrat::keys::{GalleryKey, JournalKey, UserKey, ViewKey};
+
+use super::gallery::GalleryPage;
+use super::journal::JournalPage;
+use super::user::UserPage;
+use super::view::ViewPage;
+
+/// How many pages stay alive after they're navigated away from.
+const CACHE_SIZE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    Home,
+    View(ViewKey),
+    Journal(JournalKey),
+    User(UserKey),
+    Gallery(GalleryKey),
+}
+
+impl From<Link> for Route {
+    fn from(link: Link) -> Self {
+        match link {
+            Link::View(key) => Route::View(key),
+            Link::Journal(key) => Route::Journal(key),
+            Link::User(key) => Route::User(key),
+            Link::Gallery(key) => Route::Gallery(key),
+        }
+    }
+}
+
+impl Route {
+    /// A name for the route until its page has something better.
+    fn title(&self) -> String {
+        match self {
+            Route::Home => gettext("Home"),
+            Route::View(_) => gettext("Submission"),
+            Route::Journal(_) => gettext("Journal"),
+            Route::User(_) => gettext("User"),
+            Route::Gallery(_) => gettext("Gallery"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Page {
+    View(Owned<ViewPage>),
+    Journal(Owned<JournalPage>),
+    User(Owned<UserPage>),
+    Gallery(Owned<GalleryPage>),
+}
+
+impl Page {
+    pub fn widget(&self) -> gtk::Widget {
+        match self {
+            Page::View(p) => p.widget().clone().upcast(),
+            Page::Journal(p) => p.widget().clone().upcast(),
+            Page::User(p) => p.widget().clone().upcast(),
+            Page::Gallery(p) => p.widget().clone().upcast(),
+        }
+    }
+
+    fn title(&self) -> Option<String> {
+        match self {
+            Page::View(p) => p.title(),
+            Page::Journal(p) => p.title(),
+            Page::User(p) => p.name(),
+            Page::Gallery(p) => p.title(),
+        }
+    }
+
+    /// Who made what's on the page.
+    fn byline(&self) -> Option<String> {
+        match self {
+            Page::View(p) => p.artist(),
+            Page::Journal(p) => p.author(),
+            Page::User(_) => None,
+            Page::Gallery(p) => p.artist(),
+        }
+    }
+}
+
+/// Back and forward history over routes, with the most recently shown
+/// pages kept around.
+#[derive(Debug)]
+pub struct Navigation {
+    entries: Vec<Route>,
+    position: usize,
+
+    /// Least recently shown first.
+    cache: Vec<(Route, Page)>,
+
+    history: gio::Menu,
+}
+
+impl Navigation {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![Route::Home],
+            position: 0,
+            cache: Vec::new(),
+            history: gio::Menu::new(),
+        }
+    }
+
+    pub fn current(&self) -> &Route {
+        &self.entries[self.position]
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.position + 1 < self.entries.len()
+    }
+
+    /// Go to `route`, dropping anything that was forward of here.
+    pub fn go(&mut self, route: Route) {
+        if *self.current() == route {
+            return;
+        }
+
+        self.entries.truncate(self.position + 1);
+        self.entries.push(route);
+        self.position += 1;
+    }
+
+    pub fn back(&mut self) -> bool {
+        self.position > 0 && self.go_to(self.position - 1)
+    }
+
+    pub fn forward(&mut self) -> bool {
+        self.go_to(self.position + 1)
+    }
+
+    /// Jump to an entry in the history, keeping the entries on either side.
+    pub fn go_to(&mut self, index: usize) -> bool {
+        if index >= self.entries.len() || index == self.position {
+            return false;
+        }
+
+        self.position = index;
+        true
+    }
+
+    /// Back to just home, returning every page that was built.
+    pub fn clear(&mut self) -> Vec<Page> {
+        self.entries.truncate(1);
+        self.position = 0;
+        self.cache.drain(..).map(|(_, p)| p).collect()
+    }
+
+    /// The page for the current route, built with `build` if it isn't cached,
+    /// or `None` for routes without a page of their own, like home.
+    ///
+    /// Also returns the pages pushed out of the cache to make room.
+    pub fn page<F>(&mut self, build: F) -> (Option<gtk::Widget>, Vec<Page>)
+    where
+        F: FnOnce(&Route) -> Option<Page>,
+    {
+        let route = self.current().clone();
+
+        let entry = match self.cache.iter().position(|(r, _)| *r == route) {
+            Some(index) => self.cache.remove(index),
+            None => match build(&route) {
+                Some(page) => (route, page),
+                None => return (None, Vec::new()),
+            },
+        };
+
+        let widget = entry.1.widget();
+        self.cache.push(entry);
+
+        let excess = self.cache.len().saturating_sub(CACHE_SIZE);
+        let evicted = self.cache.drain(..excess).map(|(_, p)| p).collect();
+
+        (Some(widget), evicted)
+    }
+
+    fn cached(&self, route: &Route) -> Option<&Page> {
+        self.cache.iter().find(|(r, _)| r == route).map(|(_, p)| p)
+    }
+
+    fn title_of(&self, route: &Route) -> String {
+        self.cached(route)
+            .and_then(Page::title)
+            .unwrap_or_else(|| route.title())
+    }
+
+    /// A title for the current page, like “Title by artist”, or `None` on
+    /// home.
+    pub fn title(&self) -> Option<String> {
+        let route = self.current();
+        if *route == Route::Home {
+            return None;
+        }
+
+        let title = self.title_of(route);
+
+        let title = match self.cached(route).and_then(Page::byline) {
+            Some(byline) => gettext("{title} by {artist}")
+                .replace("{title}", &title)
+                .replace("{artist}", &byline),
+            None => title,
+        };
+
+        Some(title)
+    }
+
+    /// The history, newest first, as a menu.
+    pub fn history(&self) -> &gio::Menu {
+        &self.history
+    }
+
+    /// Rebuild the history menu with items targeting `action`, picking up
+    /// titles of pages that have loaded since.
+    pub fn sync_history(&self, action: &str) {
+        self.history.remove_all();
+
+        // Newest first, like a browser.
+        for (index, route) in self.entries.iter().enumerate().rev() {
+            let title = self.title_of(route);
+            let item = gio::MenuItem::new(Some(&title), None);
+            item.set_action_and_target_value(
+                Some(action),
+                Some(&(index as u32).to_variant()),
+            );
+            self.history.append_item(&item);
+        }
+    }
+}
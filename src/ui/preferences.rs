@@ -0,0 +1,616 @@
+use crate::settings::{
+    Cache, Collapse, Layout, Settings, Store, ThumbnailSize, Visibility,
+};
+
+use gettextrs::gettext;
+
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use super::inbox::Inbox;
+
+/// Window for changing the [`Settings`].
+///
+/// Every change is saved as soon as it's made.
+#[derive(Debug)]
+pub struct Preferences {
+    window: adw::PreferencesWindow,
+}
+
+impl Preferences {
+    pub fn new<W>(settings: &Store, parent: &W) -> Self
+    where
+        W: IsA<gtk::Window>,
+    {
+        let current = settings.get();
+
+        let window = adw::PreferencesWindowBuilder::new()
+            .transient_for(parent)
+            .modal(true)
+            .build();
+
+        window.add(&Self::inbox_page(settings, &current));
+        window.add(&Self::blocklist_page(settings, &current));
+        window.add(&Self::notifications_page(settings, &current));
+        window.add(&Self::network_page(settings, &current));
+
+        Self { window }
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+
+    fn inbox_page(store: &Store, current: &Settings) -> adw::PreferencesPage {
+        let sizes = ThumbnailSize::ALL
+            .iter()
+            .map(|&size| {
+                let label = match size {
+                    ThumbnailSize::Small => gettext("Small"),
+                    ThumbnailSize::Medium => gettext("Medium"),
+                    ThumbnailSize::Large => gettext("Large"),
+                    ThumbnailSize::ExtraLarge => gettext("Extra Large"),
+                };
+                (size, label)
+            })
+            .collect();
+
+        let s = store.clone();
+        let size = combo_row(
+            &gettext("Thumbnail Size"),
+            sizes,
+            current.inbox.thumbnail_size,
+            move |size| update(&s, |s| s.inbox.thumbnail_size = size),
+        );
+
+        let layouts = vec![
+            (Layout::List, gettext("List")),
+            (Layout::Grid, gettext("Grid")),
+        ];
+
+        let s = store.clone();
+        let layout = combo_row(
+            &gettext("Layout"),
+            layouts,
+            current.inbox.layout,
+            move |layout| update(&s, |s| s.inbox.layout = layout),
+        );
+
+        let collapses = vec![
+            (Collapse::Off, gettext("Never")),
+            (Collapse::Consecutive, gettext("When in a Row")),
+            (Collapse::All, gettext("Always")),
+        ];
+
+        let s = store.clone();
+        let collapse = combo_row(
+            &gettext("Collapse by Artist"),
+            collapses,
+            current.inbox.collapse,
+            move |c| update(&s, |s| s.inbox.collapse = c),
+        );
+
+        let s = store.clone();
+        let load_ahead = spin_row(
+            &gettext("Load Ahead"),
+            &gettext("Pixels from the end of the list to load more at"),
+            (0., 5000., 100.),
+            f64::from(current.inbox.load_ahead),
+            move |px| update(&s, |s| s.inbox.load_ahead = px as u32),
+        );
+
+        let display = group(&gettext("Display"));
+        display.add(&size);
+        display.add(&layout);
+        display.add(&collapse);
+        display.add(&load_ahead);
+
+        let visibilities = || {
+            vec![
+                (Visibility::Show, gettext("Show")),
+                (Visibility::Blur, gettext("Blur")),
+                (Visibility::Hide, gettext("Hide")),
+            ]
+        };
+
+        let s = store.clone();
+        let general = combo_row(
+            &gettext("General Submissions"),
+            visibilities(),
+            current.content.general,
+            move |v| update(&s, |s| s.content.general = v),
+        );
+
+        let s = store.clone();
+        let mature = combo_row(
+            &gettext("Mature Submissions"),
+            visibilities(),
+            current.content.mature,
+            move |v| update(&s, |s| s.content.mature = v),
+        );
+
+        let s = store.clone();
+        let adult = combo_row(
+            &gettext("Adult Submissions"),
+            visibilities(),
+            current.content.adult,
+            move |v| update(&s, |s| s.content.adult = v),
+        );
+
+        let s = store.clone();
+        let safe_mode = switch_row(
+            &gettext("Safe Mode"),
+            &gettext("Blur mature and adult submissions that would be shown"),
+            current.content.safe_mode,
+            move |on| update(&s, |s| s.content.safe_mode = on),
+        );
+
+        let content = group(&gettext("Content"));
+        content.add(&general);
+        content.add(&mature);
+        content.add(&adult);
+        content.add(&safe_mode);
+
+        let page = adw::PreferencesPageBuilder::new()
+            .title(&gettext("Inbox"))
+            .icon_name("mail-inbox-symbolic")
+            .build();
+        page.add(&display);
+        page.add(&content);
+        page
+    }
+
+    fn blocklist_page(
+        store: &Store,
+        current: &Settings,
+    ) -> adw::PreferencesPage {
+        let blocklist = &current.blocklist;
+
+        let artists = word_list(
+            store,
+            &gettext("Artists"),
+            &gettext("Artist name"),
+            &blocklist.artists,
+            |s| &mut s.blocklist.artists,
+        );
+
+        let keywords = word_list(
+            store,
+            &gettext("Keywords"),
+            &gettext("Word in the title"),
+            &blocklist.keywords,
+            |s| &mut s.blocklist.keywords,
+        );
+        keywords.set_description(Some(&gettext(
+            "Titles are always checked. Tags are only checked once a \
+             submission's page has been loaded, like when searching by \
+             category.",
+        )));
+
+        let s = store.clone();
+        let auto_clear = switch_row(
+            &gettext("Remove from Inbox"),
+            &gettext("Also remove blocked submissions from the site's inbox"),
+            blocklist.auto_clear,
+            move |on| update(&s, |s| s.blocklist.auto_clear = on),
+        );
+
+        let site = group(&gettext("Site"));
+        site.add(&auto_clear);
+
+        let page = adw::PreferencesPageBuilder::new()
+            .title(&gettext("Blocklist"))
+            .icon_name("action-unavailable-symbolic")
+            .build();
+        page.add(&artists);
+        page.add(&keywords);
+        page.add(&site);
+        page
+    }
+
+    fn notifications_page(
+        store: &Store,
+        current: &Settings,
+    ) -> adw::PreferencesPage {
+        let notifications = &current.notifications;
+
+        let intervals = Inbox::INTERVALS
+            .iter()
+            .map(|&m| (m, Inbox::interval_label(m)))
+            .collect();
+
+        let s = store.clone();
+        let interval = combo_row(
+            &gettext("Check for New Items"),
+            intervals,
+            notifications.poll_interval,
+            move |m| update(&s, |s| s.notifications.poll_interval = m),
+        );
+
+        let s = store.clone();
+        let notify = switch_row(
+            &gettext("Show Notifications"),
+            &gettext("When a check finds something new"),
+            notifications.notify,
+            move |on| update(&s, |s| s.notifications.notify = on),
+        );
+
+        let s = store.clone();
+        let background = switch_row(
+            &gettext("Run in Background"),
+            &gettext("Keep checking after the window is closed"),
+            notifications.run_in_background,
+            move |on| update(&s, |s| s.notifications.run_in_background = on),
+        );
+
+        let checks = group(&gettext("Checks"));
+        checks.add(&interval);
+        checks.add(&notify);
+        checks.add(&background);
+
+        let page = adw::PreferencesPageBuilder::new()
+            .title(&gettext("Notifications"))
+            .icon_name("preferences-system-notifications-symbolic")
+            .build();
+        page.add(&checks);
+        page
+    }
+
+    fn network_page(store: &Store, current: &Settings) -> adw::PreferencesPage {
+        let s = store.clone();
+        let cache = spin_row(
+            &gettext("Image Cache Size"),
+            &gettext("Megabytes of images kept for each account"),
+            (16., f64::from(Cache::MAX_SIZE), 64.),
+            f64::from(current.cache.max_size),
+            move |mb| update(&s, |s| s.cache.max_size = mb as u32),
+        );
+
+        let restart = gettext("Takes effect the next time the app starts");
+
+        let location = folder_row(
+            store,
+            &gettext("Image Cache Location"),
+            &restart,
+            current.cache.location.clone(),
+        );
+
+        let storage = group(&gettext("Storage"));
+        storage.add(&cache);
+        storage.add(&location);
+
+        let s = store.clone();
+        let concurrent = spin_row(
+            &gettext("Simultaneous Requests"),
+            &restart,
+            (1., 16., 1.),
+            f64::from(current.network.max_concurrent),
+            move |n| update(&s, |s| s.network.max_concurrent = n as u32),
+        );
+
+        let s = store.clone();
+        let rate = spin_row(
+            &gettext("Requests per Second"),
+            &restart,
+            (0., 20., 0.5),
+            current.network.requests_per_second,
+            move |n| update(&s, |s| s.network.requests_per_second = n),
+        );
+
+        let network = group(&gettext("Network"));
+        network.add(&concurrent);
+        network.add(&rate);
+
+        let page = adw::PreferencesPageBuilder::new()
+            .title(&gettext("Network"))
+            .icon_name("network-workgroup-symbolic")
+            .build();
+        page.add(&storage);
+        page.add(&network);
+        page
+    }
+}
+
+fn update<F>(store: &Store, func: F)
+where
+    F: FnOnce(&mut Settings),
+{
+    if let Err(e) = store.update(func) {
+        log::warn!("unable to store settings: {}", e);
+    }
+}
+
+fn group(title: &str) -> adw::PreferencesGroup {
+    adw::PreferencesGroupBuilder::new().title(title).build()
+}
+
+/// Which list of words in the settings a [`word_list`] edits.
+type WordsField = fn(&mut Settings) -> &mut Vec<String>;
+
+/// A group with a row for each of `words`, each with a button to remove
+/// it, and an entry to add more.
+fn word_list(
+    store: &Store,
+    title: &str,
+    placeholder: &str,
+    words: &[String],
+    field: WordsField,
+) -> adw::PreferencesGroup {
+    let group = group(title);
+
+    let entry = gtk::EntryBuilder::new()
+        .placeholder_text(placeholder)
+        .hexpand(true)
+        .valign(gtk::Align::Center)
+        .build();
+
+    let add_btn = gtk::ButtonBuilder::new()
+        .icon_name("list-add-symbolic")
+        .tooltip_text(&gettext("Add"))
+        .valign(gtk::Align::Center)
+        .build();
+
+    let row = adw::ActionRow::new();
+    row.add_suffix(&entry);
+    row.add_suffix(&add_btn);
+    group.add(&row);
+
+    for word in words {
+        group.add(&word_row(store, &group, word, field));
+    }
+
+    let store = store.clone();
+    let weak_group = group.downgrade();
+    let add = move |entry: &gtk::Entry| {
+        let word = entry.text().trim().to_owned();
+        let group = match weak_group.upgrade() {
+            Some(g) if !word.is_empty() => g,
+            _ => return,
+        };
+
+        let mut added = false;
+        update(&store, |s| {
+            let words = field(s);
+            if !words.iter().any(|w| w.eq_ignore_ascii_case(&word)) {
+                words.push(word.clone());
+                added = true;
+            }
+        });
+
+        if added {
+            group.add(&word_row(&store, &group, &word, field));
+        }
+
+        entry.set_text("");
+    };
+
+    let weak_entry = entry.downgrade();
+    let add_clone = add.clone();
+    add_btn.connect_clicked(move |_| {
+        if let Some(entry) = weak_entry.upgrade() {
+            add_clone(&entry);
+        }
+    });
+
+    entry.connect_activate(add);
+
+    group
+}
+
+fn word_row(
+    store: &Store,
+    group: &adw::PreferencesGroup,
+    word: &str,
+    field: WordsField,
+) -> adw::ActionRow {
+    let remove_btn = gtk::ButtonBuilder::new()
+        .icon_name("edit-delete-symbolic")
+        .tooltip_text(&gettext("Remove"))
+        .valign(gtk::Align::Center)
+        .build();
+    remove_btn.add_css_class("flat");
+
+    let row = adw::ActionRowBuilder::new().title(word).build();
+    row.add_suffix(&remove_btn);
+
+    let store = store.clone();
+    let word = word.to_owned();
+    let weak_group = group.downgrade();
+    let weak_row = row.downgrade();
+    remove_btn.connect_clicked(move |_| {
+        update(&store, |s| field(s).retain(|w| *w != word));
+
+        if let (Some(group), Some(row)) =
+            (weak_group.upgrade(), weak_row.upgrade())
+        {
+            group.remove(&row);
+        }
+    });
+
+    row
+}
+
+fn combo_row<T, F>(
+    title: &str,
+    options: Vec<(T, String)>,
+    current: T,
+    on_change: F,
+) -> adw::ActionRow
+where
+    T: 'static + Copy + PartialEq,
+    F: 'static + Fn(T),
+{
+    let combo = gtk::ComboBoxTextBuilder::new()
+        .valign(gtk::Align::Center)
+        .build();
+
+    for (_, label) in options.iter() {
+        combo.append_text(label);
+    }
+
+    let active = options.iter().position(|(v, _)| *v == current);
+    combo.set_active(active.map(|a| a as u32));
+
+    combo.connect_changed(move |combo| {
+        let index = match combo.active() {
+            Some(i) => i as usize,
+            None => return,
+        };
+
+        if let Some(&(value, _)) = options.get(index) {
+            on_change(value);
+        }
+    });
+
+    let row = adw::ActionRowBuilder::new().title(title).build();
+    row.add_suffix(&combo);
+    row
+}
+
+fn switch_row<F>(
+    title: &str,
+    subtitle: &str,
+    active: bool,
+    on_change: F,
+) -> adw::ActionRow
+where
+    F: 'static + Fn(bool),
+{
+    let switch = gtk::SwitchBuilder::new()
+        .active(active)
+        .valign(gtk::Align::Center)
+        .build();
+
+    switch.connect_property_active_notify(move |s| on_change(s.is_active()));
+
+    let row = adw::ActionRowBuilder::new()
+        .title(title)
+        .subtitle(subtitle)
+        .activatable_widget(&switch)
+        .build();
+    row.add_suffix(&switch);
+    row
+}
+
+/// A row showing the image cache location, with buttons to choose another
+/// folder or go back to the default one.
+fn folder_row(
+    store: &Store,
+    title: &str,
+    subtitle: &str,
+    current: Option<PathBuf>,
+) -> adw::ActionRow {
+    let label = gtk::LabelBuilder::new()
+        .ellipsize(gtk::pango::EllipsizeMode::Start)
+        .max_width_chars(24)
+        .valign(gtk::Align::Center)
+        .build();
+    label.add_css_class("dim-label");
+
+    let show = {
+        let label = label.clone();
+        move |location: Option<&PathBuf>| {
+            let text = match location {
+                Some(l) => l.display().to_string(),
+                None => gettext("Default"),
+            };
+            label.set_text(&text);
+        }
+    };
+    show(current.as_ref());
+
+    let choose_btn = gtk::ButtonBuilder::new()
+        .label(&gettext("Choose…"))
+        .valign(gtk::Align::Center)
+        .build();
+
+    let reset_btn = gtk::ButtonBuilder::new()
+        .icon_name("edit-undo-symbolic")
+        .tooltip_text(&gettext("Use the Default Location"))
+        .valign(gtk::Align::Center)
+        .build();
+
+    let s = store.clone();
+    let show_clone = show.clone();
+    reset_btn.connect_clicked(move |_| {
+        update(&s, |s| s.cache.location = None);
+        show_clone(None);
+    });
+
+    // Kept until it answers, since nothing else holds on to it.
+    let chooser: Rc<RefCell<Option<gtk::FileChooserNative>>> =
+        Default::default();
+
+    let s = store.clone();
+    choose_btn.connect_clicked(move |btn| {
+        let parent = btn.root().and_then(|r| r.downcast::<gtk::Window>().ok());
+
+        let dialog = gtk::FileChooserNative::new(
+            Some(&gettext("Image Cache Location")),
+            parent.as_ref(),
+            gtk::FileChooserAction::SelectFolder,
+            Some(&gettext("Select")),
+            None,
+        );
+
+        let s = s.clone();
+        let show = show.clone();
+        let chooser_clone = chooser.clone();
+        dialog.connect_response(move |dialog, response| {
+            chooser_clone.replace(None);
+
+            if response != gtk::ResponseType::Accept {
+                return;
+            }
+
+            if let Some(path) = dialog.file().and_then(|f| f.path()) {
+                show(Some(&path));
+                update(&s, |s| s.cache.location = Some(path));
+            }
+        });
+
+        dialog.show();
+        chooser.replace(Some(dialog));
+    });
+
+    let row = adw::ActionRowBuilder::new()
+        .title(title)
+        .subtitle(subtitle)
+        .build();
+    row.add_suffix(&label);
+    row.add_suffix(&choose_btn);
+    row.add_suffix(&reset_btn);
+    row
+}
+
+/// A row with a spin button, limited to `(min, max, step)`.
+fn spin_row<F>(
+    title: &str,
+    subtitle: &str,
+    (min, max, step): (f64, f64, f64),
+    value: f64,
+    on_change: F,
+) -> adw::ActionRow
+where
+    F: 'static + Fn(f64),
+{
+    let spin = gtk::SpinButton::with_range(min, max, step);
+    spin.set_value(value);
+    spin.set_valign(gtk::Align::Center);
+
+    if step < 1. {
+        spin.set_digits(1);
+    }
+
+    spin.connect_value_changed(move |s| on_change(s.value()));
+
+    let row = adw::ActionRowBuilder::new()
+        .title(title)
+        .subtitle(subtitle)
+        .build();
+    row.add_suffix(&spin);
+    row
+}
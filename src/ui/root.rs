@@ -1,112 +1,1179 @@
-use crate::bridge::Client;
+use crate::bridge::errors::LoginError;
+use crate::bridge::{Client, Event};
+use crate::link::Link;
+use crate::logging;
 use crate::ptr::{Owned, Wrap};
-use crate::util::Util;
+use crate::secrets::{self, Backend, Secrets, SecretsExt};
+use crate::settings::{Layout, Window};
+use crate::util::{Tasks, Util};
 
 use gettextrs::gettext;
 
 use gio::prelude::*;
 
+use glib::{StaticVariantType, ToVariant};
+
 use gtk::prelude::*;
 
+use std::cell::{Cell, RefCell};
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use super::accounts::AccountSwitcher;
+use super::background::Background;
+use super::gallery::GalleryPage;
 use super::home::Home;
+use super::inbox::Inbox;
+use super::journal::JournalPage;
+use super::login::Login;
+use super::navigation::{Navigation, Page, Route};
+use super::preferences::Preferences;
+use super::user::UserPage;
+use super::view::ViewPage;
+
+use tokio::sync::broadcast::error::RecvError;
 
 #[derive(Debug)]
 pub struct Root {
-    home: Owned<Home>,
+    home: RefCell<Owned<Home>>,
+    navigation: RefCell<Navigation>,
+    accounts: AccountSwitcher,
 
     titlebar: gtk::HeaderBar,
+    switcher_title: adw::ViewSwitcherTitle,
     window: gtk::ApplicationWindow,
+    leaflet: adw::Leaflet,
+    sidebar: gtk::Box,
     stack: gtk::Stack,
+    empty: gtk::Label,
+
+    status: gtk::InfoBar,
+    status_label: gtk::Label,
 
-    back_btn: gtk::Button,
     back_action: gio::SimpleAction,
+    forward_action: gio::SimpleAction,
+    go_to_action: gio::SimpleAction,
+
+    login: RefCell<Option<(gtk::Dialog, Owned<Login>)>>,
+
+    /// Whether the bridge is holding back requests until we log in again.
+    expired: Cell<bool>,
 
     util: Util,
+    tasks: Tasks,
 }
 
 impl Root {
     pub const BACK: &'static str = "win.back";
+    pub const FORWARD: &'static str = "win.forward";
+    pub const GO_TO: &'static str = "win.go-to";
+    pub const SWITCH_ACCOUNT: &'static str = "win.switch-account";
+    pub const ADD_ACCOUNT: &'static str = "win.add-account";
+    pub const SECRETS_BACKEND: &'static str = "win.secrets-backend";
+    pub const LOGOUT: &'static str = "win.logout";
+    pub const DEBUG_LOGGING: &'static str = "win.debug-logging";
+    pub const SHORTCUTS: &'static str = "win.show-help-overlay";
+    pub const PREFERENCES: &'static str = "win.preferences";
+    pub const SAFE_MODE: &'static str = "win.safe-mode";
+    pub const GRID: &'static str = "win.grid";
+
+    /// Narrowest and widest the inbox can be dragged to, in pixels.
+    const PANE_WIDTHS: (i32, i32) = (280, 1200);
 
     pub fn new(
         application: &gtk::Application,
         util: Util,
     ) -> Result<Owned<Self>, glib::Error> {
+        let state = util.settings().get().window;
+
         let home = Home::new(util.clone());
+        if let Some(ref tab) = state.tab {
+            home.set_tab(tab);
+        }
+
+        let navigation = Navigation::new();
 
         let back_action = gio::SimpleAction::new("back", None);
+        let forward_action = gio::SimpleAction::new("forward", None);
+        let go_to_action = gio::SimpleAction::new_stateful(
+            "go-to",
+            Some(&u32::static_variant_type()),
+            &0u32.to_variant(),
+        );
 
         let back_btn = gtk::ButtonBuilder::new()
             .action_name(Self::BACK)
             .icon_name("go-previous")
             .build();
 
-        back_action.set_enabled(false);
+        let forward_btn = gtk::ButtonBuilder::new()
+            .action_name(Self::FORWARD)
+            .icon_name("go-next")
+            .build();
+
+        let history_btn = gtk::MenuButtonBuilder::new()
+            .icon_name("document-open-recent-symbolic")
+            .tooltip_text(&gettext("History"))
+            .menu_model(navigation.history())
+            .build();
+
+        application.set_accels_for_action(Self::BACK, &["<Alt>Left", "Back"]);
+        application
+            .set_accels_for_action(Self::FORWARD, &["<Alt>Right", "Forward"]);
+        application
+            .set_accels_for_action(Self::SHORTCUTS, &["<Primary>question"]);
+        application
+            .set_accels_for_action(Self::PREFERENCES, &["<Primary>comma"]);
+        application
+            .set_accels_for_action(Self::SAFE_MODE, &["<Primary><Shift>s"]);
+
+        let switch_account_action = gio::SimpleAction::new(
+            "switch-account",
+            Some(&String::static_variant_type()),
+        );
+        let add_account_action = gio::SimpleAction::new("add-account", None);
+
+        let backend = util.settings().get().secrets.backend;
+        let secrets_backend_action = gio::SimpleAction::new_stateful(
+            "secrets-backend",
+            Some(&String::static_variant_type()),
+            &backend.as_str().to_variant(),
+        );
+
+        let logout_action = gio::SimpleAction::new("logout", None);
+        let preferences_action = gio::SimpleAction::new("preferences", None);
+
+        let grid = util.settings().get().inbox.layout == Layout::Grid;
+        let grid_action =
+            gio::SimpleAction::new_stateful("grid", None, &grid.to_variant());
+
+        let safe_mode = util.settings().get().content.safe_mode;
+        let safe_mode_action = gio::SimpleAction::new_stateful(
+            "safe-mode",
+            None,
+            &safe_mode.to_variant(),
+        );
+
+        let debug_logging_action = gio::SimpleAction::new_stateful(
+            "debug-logging",
+            None,
+            &logging::is_debug().to_variant(),
+        );
 
-        let titlebar = gtk::HeaderBarBuilder::new().build();
+        let accounts = AccountSwitcher::new();
+
+        let menu_btn = gtk::MenuButtonBuilder::new()
+            .icon_name("open-menu-symbolic")
+            .menu_model(&Self::menu())
+            .build();
+
+        let switcher_title = adw::ViewSwitcherTitleBuilder::new()
+            .title(&gettext("Labrat"))
+            .build();
+
+        let safe_mode_btn = gtk::ToggleButtonBuilder::new()
+            .action_name(Self::SAFE_MODE)
+            .icon_name("security-high-symbolic")
+            .tooltip_text(&gettext("Safe Mode"))
+            .build();
+
+        let grid_btn = gtk::ToggleButtonBuilder::new()
+            .action_name(Self::GRID)
+            .icon_name("view-grid-symbolic")
+            .tooltip_text(&gettext("Show as Grid"))
+            .build();
+
+        let titlebar = gtk::HeaderBarBuilder::new()
+            .title_widget(&switcher_title)
+            .build();
 
         titlebar.pack_start(&back_btn);
+        titlebar.pack_start(&forward_btn);
+        titlebar.pack_start(&history_btn);
+        titlebar.pack_end(&menu_btn);
+        titlebar.pack_end(accounts.widget());
+        titlebar.pack_end(&safe_mode_btn);
+        titlebar.pack_end(&grid_btn);
+
+        let empty = gtk::LabelBuilder::new()
+            .label(&gettext("Choose a submission to see it here"))
+            .wrap(true)
+            .build();
+        empty.add_css_class("dim-label");
+
+        let stack = gtk::StackBuilder::new().hexpand(true).build();
+        stack.add_child(&empty);
+
+        // Holds the inbox, with a handle on its edge for resizing it.
+        let sidebar = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        let (min, max) = Self::PANE_WIDTHS;
+        sidebar.set_size_request(state.pane_width.clamp(min, max), -1);
+
+        let separator = gtk::SeparatorBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .halign(gtk::Align::Center)
+            .build();
+        let handle = gtk::BoxBuilder::new().width_request(6).build();
+        handle.append(&separator);
+        handle.set_cursor_from_name(Some("col-resize"));
+        sidebar.append(&handle);
+
+        let drag = gtk::GestureDrag::new();
+        let start = Rc::new(Cell::new(0));
+
+        let sidebar_clone = sidebar.clone();
+        let start_clone = start.clone();
+        drag.connect_drag_begin(move |_, _, _| {
+            start_clone.set(sidebar_clone.width());
+        });
 
-        let stack = gtk::Stack::new();
+        let sidebar_clone = sidebar.clone();
+        drag.connect_drag_update(move |_, x, _| {
+            let width = (start.get() + x as i32).clamp(min, max);
+            sidebar_clone.set_size_request(width, -1);
+        });
+        handle.add_controller(&drag);
+
+        // Inbox on the left, whatever was opened from it on the right, or
+        // just one of them at a time when there isn't room for both.
+        let leaflet = adw::LeafletBuilder::new().vexpand(true).build();
+        leaflet.append(&sidebar);
+        leaflet.append(&stack);
+
+        let status_label = gtk::Label::new(None);
+        let status = gtk::InfoBarBuilder::new()
+            .message_type(gtk::MessageType::Info)
+            .revealed(false)
+            .build();
+        status.add_child(&status_label);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        content.append(&status);
+        content.append(&leaflet);
 
         let window = gtk::ApplicationWindowBuilder::new()
             .application(application)
-            .title(&gettext("First GTK Program"))
-            .default_height(1440)
-            .default_width(720)
-            .child(&stack)
+            .title(&gettext("Labrat"))
+            .default_width(state.width)
+            .default_height(state.height)
+            .maximized(state.maximized)
+            .child(&content)
             .build();
 
         window.set_titlebar(Some(&titlebar));
+
+        let builder = gtk::Builder::from_string(include_str!("shortcuts.ui"));
+        let shortcuts: gtk::ShortcutsWindow =
+            builder.get_object("shortcuts").unwrap();
+        window.set_help_overlay(Some(&shortcuts));
+
         window.add_action(&back_action);
+        window.add_action(&forward_action);
+        window.add_action(&go_to_action);
+        window.add_action(&switch_account_action);
+        window.add_action(&add_account_action);
+        window.add_action(&secrets_backend_action);
+        window.add_action(&logout_action);
+        window.add_action(&debug_logging_action);
+        window.add_action(&preferences_action);
+        window.add_action(&safe_mode_action);
+        window.add_action(&grid_action);
 
         let owned = Owned::new(Root {
             back_action: back_action.clone(),
-            back_btn,
+            forward_action: forward_action.clone(),
+            go_to_action: go_to_action.clone(),
             titlebar,
+            switcher_title: switcher_title.clone(),
             window,
+            leaflet,
+            sidebar,
             stack,
-            home,
+            empty,
+            status,
+            status_label,
+            home: RefCell::new(home),
+            navigation: RefCell::new(navigation),
+            accounts,
+            login: Default::default(),
+            expired: Cell::new(false),
             util,
+            tasks: Tasks::default(),
         });
 
         let weak = Owned::downgrade(&owned);
         back_action.connect_activate(move |_, _| {
             if let Some(root) = weak.upgrade() {
-                root.pop();
+                root.back();
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        forward_action.connect_activate(move |_, _| {
+            if let Some(root) = weak.upgrade() {
+                root.forward();
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        go_to_action.connect_change_state(move |_, value| {
+            let index = value.and_then(|v| v.get::<u32>());
+            if let (Some(root), Some(index)) = (weak.upgrade(), index) {
+                root.go_to(index as usize);
             }
         });
 
-        owned.push(owned.home.widget());
+        // The extra buttons some mice have for back and forward.
+        let buttons = gtk::GestureClick::new();
+        buttons.set_button(0);
+        let weak = Owned::downgrade(&owned);
+        buttons.connect_pressed(move |gesture, _, _, _| {
+            let root = match weak.upgrade() {
+                Some(r) => r,
+                None => return,
+            };
+
+            match gesture.current_button() {
+                8 => root.back(),
+                9 => root.forward(),
+                _ => return,
+            }
+
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+        });
+        owned.window.add_controller(&buttons);
+
+        let weak = Owned::downgrade(&owned);
+        switch_account_action.connect_activate(move |_, param| {
+            let name = param.and_then(|p| p.get::<String>());
+            if let (Some(root), Some(name)) = (weak.upgrade(), name) {
+                root.switch_account(&name);
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        add_account_action.connect_activate(move |_, _| {
+            if let Some(root) = weak.upgrade() {
+                root.log_in(&gettext("Add Account"), None);
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        secrets_backend_action.connect_change_state(move |action, value| {
+            let backend = value
+                .and_then(|v| v.get::<String>())
+                .and_then(|v| Backend::from_str(&v));
+
+            if let (Some(root), Some(backend)) = (weak.upgrade(), backend) {
+                root.set_secrets_backend(action, backend);
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        logout_action.connect_activate(move |_, _| {
+            if let Some(root) = weak.upgrade() {
+                root.confirm_logout();
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        preferences_action.connect_activate(move |_, _| {
+            if let Some(root) = weak.upgrade() {
+                Preferences::new(root.util.settings(), &root.window).show();
+            }
+        });
+
+        let settings = owned.util.settings().clone();
+        safe_mode_action.connect_change_state(move |_, value| {
+            if let Some(enabled) = value.and_then(|v| v.get::<bool>()) {
+                let result = settings.update(|s| s.content.safe_mode = enabled);
+
+                if let Err(e) = result {
+                    log::warn!("unable to store safe mode: {}", e);
+                }
+            }
+        });
+
+        let settings = owned.util.settings().clone();
+        grid_action.connect_change_state(move |_, value| {
+            if let Some(grid) = value.and_then(|v| v.get::<bool>()) {
+                let layout = if grid { Layout::Grid } else { Layout::List };
+                let result = settings.update(|s| s.inbox.layout = layout);
+
+                if let Err(e) = result {
+                    log::warn!("unable to store layout: {}", e);
+                }
+            }
+        });
+
+        debug_logging_action.connect_change_state(|action, value| {
+            if let Some(enabled) = value.and_then(|v| v.get::<bool>()) {
+                logging::set_debug(enabled);
+                action.set_state(&enabled.to_variant());
+            }
+        });
+
+        // The switcher moves to the bottom of the inbox when the header bar
+        // gets too narrow for it.
+        let weak = Owned::downgrade(&owned);
+        switcher_title.connect_property_title_visible_notify(move |title| {
+            if let Some(root) = weak.upgrade() {
+                let home = root.home.borrow();
+                home.reveal_switcher(title.is_title_visible());
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        owned.window.connect_close_request(move |_| {
+            if let Some(root) = weak.upgrade() {
+                root.save_state();
+            }
+
+            glib::signal::Inhibit(false)
+        });
+
+        owned.attach_home();
+        owned.show_current();
+        owned.watch_events();
+        owned.watch_settings(safe_mode_action, grid_action);
 
         Ok(owned)
     }
+
+    fn menu() -> gio::Menu {
+        let backends = gio::Menu::new();
+
+        let labels = [
+            (Backend::Auto, gettext("Automatically")),
+            (Backend::SecretService, gettext("Secret Service")),
+            (Backend::File, gettext("Encrypted File")),
+        ];
+
+        for (backend, label) in labels.iter() {
+            let item = gio::MenuItem::new(Some(label), None);
+            item.set_action_and_target_value(
+                Some(Self::SECRETS_BACKEND),
+                Some(&backend.as_str().to_variant()),
+            );
+            backends.append_item(&item);
+        }
+
+        let account = gio::Menu::new();
+        account.append(Some(&gettext("Log Out…")), Some(Self::LOGOUT));
+
+        let intervals = gio::Menu::new();
+
+        for &minutes in Inbox::INTERVALS {
+            let label = Inbox::interval_label(minutes);
+            let item = gio::MenuItem::new(Some(&label), None);
+            item.set_action_and_target_value(
+                Some(Inbox::POLL_INTERVAL),
+                Some(&minutes.to_variant()),
+            );
+            intervals.append_item(&item);
+        }
+
+        let app = gio::Menu::new();
+        app.append(
+            Some(&gettext("Run in Background")),
+            Some(Background::RUN_IN_BACKGROUND),
+        );
+        app.append(Some(&gettext("Preferences")), Some(Self::PREFERENCES));
+        app.append(Some(&gettext("Debug Logging")), Some(Self::DEBUG_LOGGING));
+        app.append(Some(&gettext("Keyboard Shortcuts")), Some(Self::SHORTCUTS));
+        app.append(Some(&gettext("Quit")), Some(Background::QUIT));
+
+        let menu = gio::Menu::new();
+        menu.append_submenu(Some(&gettext("Store Cookies In")), &backends);
+        menu.append_submenu(Some(&gettext("Check for New Items")), &intervals);
+        menu.append_section(None, &account);
+        menu.append_section(None, &app);
+        menu
+    }
 }
 
 impl Wrap<Root> {
-    fn pop(&self) {
-        if let Some(child) = self.stack.visible_child() {
-            self.stack.remove(&child);
-            let pages = self.stack.pages();
-            let len = pages.n_items();
+    fn watch_events(&self) {
+        let mut events = self.util.client().events();
+        let weak = self.weak();
+
+        let task = self.util.spawn_local::<_, RecvError>(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(e) => e,
+                    Err(RecvError::Lagged(_)) => continue,
+                    // The bridge has stopped.
+                    Err(RecvError::Closed) => return Ok(()),
+                };
 
-            if len <= 1 {
-                self.back_action.set_enabled(false);
+                match weak.upgrade() {
+                    Some(this) => this.on_event(event),
+                    None => return Ok(()),
+                }
             }
+        });
+
+        self.tasks.push(task);
+    }
 
-            if len > 0 {
-                pages.select_item(len - 1, true);
+    /// Keep the safe mode and grid toggles in step with the settings, which
+    /// the preferences window can change too.
+    fn watch_settings(
+        &self,
+        safe_mode_action: gio::SimpleAction,
+        grid_action: gio::SimpleAction,
+    ) {
+        let mut settings = self.util.settings().subscribe();
+
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            while settings.changed().await.is_ok() {
+                let settings = settings.borrow();
+
+                let enabled = settings.content.safe_mode;
+                safe_mode_action.set_state(&enabled.to_variant());
+
+                let grid = settings.inbox.layout == Layout::Grid;
+                grid_action.set_state(&grid.to_variant());
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    fn on_event(&self, event: Event) {
+        match event {
+            Event::RateLimited { .. } => {
+                self.status_label.set_text(&gettext(
+                    "FurAffinity asked to slow down, waiting to retry…",
+                ));
+                self.status.set_message_type(gtk::MessageType::Info);
+                self.status.set_revealed(true);
+            }
+
+            Event::Resumed => self.status.set_revealed(false),
+
+            Event::SessionExpired => {
+                self.expired.set(true);
+                let account = self.util.account();
+                self.log_in(&gettext("Session Expired"), account.as_deref());
             }
         }
     }
 
-    pub fn push<P>(&self, child: &P)
-    where
-        P: IsA<gtk::Widget>,
-    {
-        self.stack.add_child(child);
-        self.stack.set_visible_child(child);
-        if self.stack.pages().n_items() > 1 {
-            self.back_action.set_enabled(true);
+    /// Pick an account to use, asking for the secrets passphrase first if
+    /// needed.
+    pub fn start(&self) {
+        let weak = self.weak();
+        let util = self.util.clone();
+        let task = self.util.spawn_local::<_, secrets::Error>(async move {
+            let accounts = Secrets::with(&util, |s| {
+                if s.needs_unlock() {
+                    return Ok(None);
+                }
+
+                // A messy keyring isn't worth refusing to start over.
+                if let Err(e) = s.tidy() {
+                    log::warn!("unable to tidy secrets: {}", e);
+                }
+
+                s.accounts().map(Some)
+            })
+            .await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            let accounts = match accounts {
+                Ok(Some(a)) => a,
+                Ok(None) => {
+                    this.unlock();
+                    return Ok(());
+                }
+                Err(e) => {
+                    this.show_error(&format!(
+                        "{}: {}",
+                        gettext("Unable to read cookies"),
+                        e
+                    ));
+                    return Ok(());
+                }
+            };
+
+            // Prefer the account used last, falling back to any account.
+            let account = this
+                .util
+                .account()
+                .filter(|a| accounts.contains(a))
+                .or_else(|| accounts.first().cloned());
+
+            let active = this.util.account();
+            this.accounts.set_accounts(&accounts, active.as_deref());
+
+            match account {
+                None => this.log_in(&gettext("Log In"), None),
+                Some(account) => this.switch_account(&account),
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Ask for the passphrase of the secrets file, then `start`.
+    fn unlock(&self) {
+        let description = gtk::LabelBuilder::new()
+            .label(&gettext(
+                "Cookies are stored in a file encrypted with a passphrase. \
+                 If there is no file yet, one is created with the passphrase \
+                 you enter.",
+            ))
+            .wrap(true)
+            .max_width_chars(40)
+            .xalign(0.)
+            .build();
+
+        let entry = gtk::PasswordEntryBuilder::new()
+            .placeholder_text(&gettext("Passphrase"))
+            .activates_default(true)
+            .build();
+
+        let error = gtk::LabelBuilder::new()
+            .wrap(true)
+            .xalign(0.)
+            .visible(false)
+            .build();
+        error.add_css_class("error");
+
+        let dialog = gtk::DialogBuilder::new()
+            .transient_for(&self.window)
+            .modal(true)
+            .title(&gettext("Unlock Cookies"))
+            .build();
+
+        let content = dialog.content_area();
+        content.set_spacing(12);
+        content.set_margin_top(18);
+        content.set_margin_bottom(18);
+        content.set_margin_start(18);
+        content.set_margin_end(18);
+        content.append(&description);
+        content.append(&entry);
+        content.append(&error);
+
+        dialog.add_button(&gettext("Unlock"), gtk::ResponseType::Accept);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+
+        let weak = self.weak();
+        dialog.connect_response(move |dialog, response| {
+            if response != gtk::ResponseType::Accept {
+                dialog.close();
+                return;
+            }
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return,
+            };
+
+            let passphrase = entry.text().to_string();
+            dialog.set_response_sensitive(response, false);
+
+            let dialog = dialog.clone();
+            let error = error.clone();
+            let weak = this.weak();
+            let task = this.util.spawn_local::<_, secrets::Error>(async move {
+                let this = match weak.upgrade() {
+                    Some(t) => t,
+                    None => return Ok(()),
+                };
+
+                let result =
+                    Secrets::with(&this.util, move |s| s.unlock(&passphrase))
+                        .await;
+
+                match result {
+                    Ok(()) => {
+                        dialog.close();
+                        this.start();
+                    }
+                    Err(e) => {
+                        error.set_text(&e.to_string());
+                        error.show();
+                        dialog.set_response_sensitive(response, true);
+                    }
+                }
+
+                Ok(())
+            });
+
+            this.tasks.push(task);
+        });
+
+        dialog.show();
+    }
+
+    fn set_secrets_backend(
+        &self,
+        action: &gio::SimpleAction,
+        backend: Backend,
+    ) {
+        // Takes effect for as long as the app runs even if it can't be saved.
+        let result =
+            self.util.settings().update(|s| s.secrets.backend = backend);
+
+        if let Err(e) = result {
+            log::warn!("unable to store secrets backend: {}", e);
+        }
+
+        action.set_state(&backend.as_str().to_variant());
+
+        // Cookies aren't copied between backends, so look for accounts again.
+        self.start();
+    }
+
+    fn confirm_logout(&self) {
+        let text = match self.util.account() {
+            Some(a) => format!("{} “{}”?", gettext("Log out of"), a),
+            None => gettext("Log out?"),
+        };
+
+        let dialog = gtk::MessageDialogBuilder::new()
+            .transient_for(&self.window)
+            .modal(true)
+            .message_type(gtk::MessageType::Question)
+            .text(&text)
+            .secondary_text(&gettext(
+                "The saved cookies, cached images and other data for this \
+                 account will be removed from this computer.",
+            ))
+            .build();
+
+        dialog.add_button(&gettext("Cancel"), gtk::ResponseType::Cancel);
+        let confirm =
+            dialog.add_button(&gettext("Log Out"), gtk::ResponseType::Accept);
+        confirm.add_css_class("destructive-action");
+
+        let weak = self.weak();
+        dialog.connect_response(move |dialog, response| {
+            dialog.close();
+
+            if response != gtk::ResponseType::Accept {
+                return;
+            }
+
+            if let Some(this) = weak.upgrade() {
+                this.logout();
+            }
+        });
+
+        dialog.show();
+    }
+
+    /// Forget the active account, then go back to logging in.
+    fn logout(&self) {
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            if let Some(account) = this.util.account() {
+                let cleared =
+                    Secrets::with(&this.util, move |s| s.clear(&account)).await;
+
+                if let Err(e) = cleared {
+                    this.show_error(&format!(
+                        "{}: {}",
+                        gettext("Unable to remove cookies"),
+                        e
+                    ));
+                    return Ok(());
+                }
+            }
+
+            // Stop using the old cookies before anything else is fetched.
+            if let Err(e) = this.util.client().logout().await {
+                this.show_error(&format!(
+                    "{}: {}",
+                    gettext("Unable to log out"),
+                    e
+                ));
+                return Ok(());
+            }
+
+            if let Err(e) = this.util.forget_account() {
+                this.show_error(&format!(
+                    "{}: {}",
+                    gettext("Unable to remove account data"),
+                    e
+                ));
+            }
+
+            this.refresh_accounts();
+            this.reload();
+            this.log_in(&gettext("Log In"), None);
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    fn show_error(&self, message: &str) {
+        self.status_label.set_text(message);
+        self.status.set_message_type(gtk::MessageType::Error);
+        self.status.set_revealed(true);
+    }
+
+    fn refresh_accounts(&self) {
+        let weak = self.weak();
+        let util = self.util.clone();
+        let task = self.util.spawn_local::<_, secrets::Error>(async move {
+            let accounts = Secrets::with(&util, |s| s.accounts()).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            let accounts = match accounts {
+                Ok(a) => a,
+                Err(e) => {
+                    this.show_error(&format!(
+                        "{}: {}",
+                        gettext("Unable to list accounts"),
+                        e
+                    ));
+                    Vec::new()
+                }
+            };
+
+            let active = this.util.account();
+            this.accounts.set_accounts(&accounts, active.as_deref());
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Rebuild every page, for example after switching accounts.
+    fn reload(&self) {
+        let pages = self.navigation.borrow_mut().clear();
+        for page in pages {
+            self.stack.remove(&page.widget());
+        }
+
+        let home = Home::new(self.util.clone());
+        if let Some(tab) = self.home.borrow().tab() {
+            home.set_tab(&tab);
+        }
+
+        let old = self.home.replace(home);
+        self.sidebar.remove(old.widget());
+
+        self.attach_home();
+        self.show_current();
+    }
+
+    /// Put the inbox in the left pane and hook it up to the window.
+    fn attach_home(&self) {
+        let home = self.home.borrow();
+
+        self.sidebar.prepend(home.widget());
+        self.switcher_title.set_stack(Some(home.stack()));
+        home.reveal_switcher(self.switcher_title.is_title_visible());
+
+        let weak = self.weak();
+        home.connect_open(move |route| {
+            if let Some(this) = weak.upgrade() {
+                this.navigate(route);
+            }
+        });
+    }
+
+    /// Start using `account` for caches and the bridge, once the bridge is
+    /// logged in with it.
+    fn activate_account(&self, account: &str) {
+        if self.util.account().as_deref() == Some(account) {
+            self.refresh_accounts();
+            return;
+        }
+
+        if let Err(e) = self.util.set_account(Some(account)) {
+            self.show_error(&format!(
+                "{}: {}",
+                gettext("Unable to switch accounts"),
+                e
+            ));
+            return;
+        }
+
+        self.refresh_accounts();
+        self.reload();
+    }
+
+    pub fn switch_account(&self, account: &str) {
+        let weak = self.weak();
+        let account = account.to_owned();
+        let task = self.util.spawn_local::<_, LoginError>(async move {
+            let util = match weak.upgrade() {
+                Some(t) => t.util.clone(),
+                None => return Ok(()),
+            };
+
+            let name = account.clone();
+            let cookie = Secrets::with(&util, move |s| s.get(&name)).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            let cookie = match cookie {
+                Ok(Some(c)) => c,
+                Ok(None) => {
+                    this.log_in(&gettext("Log In"), Some(&account));
+                    return Ok(());
+                }
+                Err(e) => {
+                    this.show_error(&format!(
+                        "{}: {}",
+                        gettext("Unable to read cookies"),
+                        e
+                    ));
+                    return Ok(());
+                }
+            };
+
+            match this.util.client().login(&cookie).await {
+                Ok(()) => this.activate_account(&account),
+                Err(e) => this.show_error(&format!(
+                    "{}: {}",
+                    gettext("Unable to switch accounts"),
+                    e
+                )),
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Ask for new cookies in a dialog, unless one is already open.
+    pub fn log_in(&self, title: &str, account: Option<&str>) {
+        if self.login.borrow().is_some() {
+            return;
+        }
+
+        let weak = self.weak();
+        let login = Login::new(self.util.clone(), account, move |account| {
+            if let Some(this) = weak.upgrade() {
+                if let Some((dialog, _)) = this.login.take() {
+                    dialog.close();
+                }
+
+                this.expired.set(false);
+                this.activate_account(account);
+            }
+        });
+
+        let dialog = gtk::DialogBuilder::new()
+            .transient_for(&self.window)
+            .modal(true)
+            .title(title)
+            .build();
+
+        let weak = self.weak();
+        dialog.connect_close_request(move |_| {
+            if let Some(this) = weak.upgrade() {
+                this.cancel_log_in();
+            }
+
+            glib::signal::Inhibit(false)
+        });
+
+        dialog.content_area().append(login.widget());
+        dialog.show();
+
+        self.login.replace(Some((dialog, login)));
+    }
+
+    /// Stay logged out after the login dialog is closed without logging in.
+    fn cancel_log_in(&self) {
+        // Logging in takes the dialog before closing it.
+        if self.login.take().is_none() {
+            return;
+        }
+
+        if !self.expired.replace(false) {
+            return;
+        }
+
+        // Let requests held back for the expired session go through without
+        // cookies, instead of waiting for a login that isn't coming.
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            if let Err(e) = this.util.client().logout().await {
+                this.show_error(&format!(
+                    "{}: {}",
+                    gettext("Unable to log out"),
+                    e
+                ));
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Show the page `uri` links to.
+    pub fn open_uri(&self, uri: &str) {
+        match Link::parse(uri) {
+            Some(link) => self.navigate(Route::from(link)),
+            None => log::warn!("unable to open {}", uri),
+        }
+    }
+
+    pub fn navigate(&self, route: Route) {
+        self.navigation.borrow_mut().go(route);
+        self.show_current();
+    }
+
+    fn back(&self) {
+        if self.navigation.borrow_mut().back() {
+            self.show_current();
+        }
+    }
+
+    fn forward(&self) {
+        if self.navigation.borrow_mut().forward() {
+            self.show_current();
+        }
+    }
+
+    fn go_to(&self, index: usize) {
+        if self.navigation.borrow_mut().go_to(index) {
+            self.show_current();
+        }
+    }
+
+    fn build_page(&self, route: &Route) -> Option<Page> {
+        let util = self.util.clone();
+
+        let weak = self.weak();
+        let on_loaded = move || {
+            if let Some(this) = weak.upgrade() {
+                this.update_titles();
+            }
+        };
+
+        let page = match route {
+            // Home lives in its own pane.
+            Route::Home => return None,
+            Route::View(key) => {
+                Page::View(ViewPage::new(util, key.clone(), on_loaded))
+            }
+            Route::Journal(key) => {
+                Page::Journal(JournalPage::new(util, key.clone(), on_loaded))
+            }
+            Route::User(key) => {
+                Page::User(UserPage::new(util, key.clone(), on_loaded))
+            }
+            Route::Gallery(key) => {
+                let weak = self.weak();
+                let on_open = move |key| {
+                    if let Some(this) = weak.upgrade() {
+                        this.navigate(Route::View(key));
+                    }
+                };
+
+                Page::Gallery(GalleryPage::new(
+                    util,
+                    key.clone(),
+                    on_loaded,
+                    on_open,
+                ))
+            }
+        };
+
+        Some(page)
+    }
+
+    /// Put the page for the current route on screen, building it if needed.
+    fn show_current(&self) {
+        let mut navigation = self.navigation.borrow_mut();
+
+        let (widget, evicted) = navigation.page(|r| self.build_page(r));
+
+        for page in evicted {
+            self.stack.remove(&page.widget());
+        }
+
+        match widget {
+            Some(widget) => {
+                if widget.parent().is_none() {
+                    self.stack.add_child(&widget);
+                }
+
+                self.stack.set_visible_child(&widget);
+                self.leaflet.set_visible_child(&self.stack);
+            }
+            None => {
+                self.stack.set_visible_child(&self.empty);
+                self.leaflet.set_visible_child(&self.sidebar);
+            }
+        }
+
+        self.back_action.set_enabled(navigation.can_go_back());
+        self.forward_action.set_enabled(navigation.can_go_forward());
+        self.go_to_action
+            .set_state(&(navigation.position() as u32).to_variant());
+
+        drop(navigation);
+        self.update_titles();
+    }
+
+    /// Name the window after the current page, and pick up titles of pages
+    /// that have loaded for the history.
+    fn update_titles(&self) {
+        let navigation = self.navigation.borrow();
+
+        let title = navigation.title().unwrap_or_else(|| gettext("Labrat"));
+        self.window.set_title(Some(&title));
+
+        navigation.sync_history(Root::GO_TO);
+    }
+
+    /// Remember how the window was left, for the next launch.
+    pub fn save_state(&self) {
+        let (width, height) = self.window.default_size();
+
+        let state = Window {
+            width,
+            height,
+            maximized: self.window.is_maximized(),
+            pane_width: self.sidebar.size_request().0,
+            tab: self.home.borrow().tab(),
+        };
+
+        let result = self.util.settings().update(|s| s.window = state);
+
+        if let Err(e) = result {
+            log::warn!("unable to store window state: {}", e);
         }
     }
 
@@ -0,0 +1,513 @@
+//! Narrowing down and ordering the submissions already loaded in a list.
+
+use gettextrs::gettext;
+
+use gtk::prelude::*;
+
+use labrat::keys::ViewKey;
+use labrat::resources::{Rating, Submission};
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    Oldest,
+    Newest,
+    Title,
+    Artist,
+}
+
+impl Sort {
+    const ALL: &'static [Self] =
+        &[Self::Oldest, Self::Newest, Self::Title, Self::Artist];
+
+    fn label(self) -> String {
+        match self {
+            Self::Oldest => gettext("Oldest First"),
+            Self::Newest => gettext("Newest First"),
+            Self::Title => gettext("By Title"),
+            Self::Artist => gettext("By Artist"),
+        }
+    }
+}
+
+/// What to put headers between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Nothing,
+    Artist,
+    Date,
+}
+
+impl Group {
+    const ALL: &'static [Self] = &[Self::Nothing, Self::Artist, Self::Date];
+
+    fn label(self) -> String {
+        match self {
+            Self::Nothing => gettext("No Groups"),
+            Self::Artist => gettext("Group by Artist"),
+            Self::Date => gettext("Group by Date"),
+        }
+    }
+}
+
+/// Whether to show submissions that are in the account's favorites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faved {
+    Any,
+    Yes,
+    No,
+}
+
+impl Faved {
+    const ALL: &'static [Self] = &[Self::Any, Self::Yes, Self::No];
+
+    fn label(self) -> String {
+        match self {
+            Self::Any => gettext("Favorited or Not"),
+            Self::Yes => gettext("Favorited"),
+            Self::No => gettext("Not Favorited"),
+        }
+    }
+
+    fn allows(self, faved: bool) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Yes => faved,
+            Self::No => !faved,
+        }
+    }
+}
+
+/// What's only known about a submission from its own page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Details {
+    pub category: String,
+    pub faved: bool,
+
+    /// When it was posted, as `YYYY-MM-DD`.
+    pub day: String,
+
+    pub tags: Vec<String>,
+}
+
+/// Which submissions to show, and in what order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Text the title or artist has to contain, in lowercase.
+    pub text: String,
+
+    /// The only artist to show.
+    pub artist: Option<String>,
+
+    pub general: bool,
+    pub mature: bool,
+    pub adult: bool,
+
+    /// The only category to show.
+    pub category: Option<String>,
+    pub faved: Faved,
+
+    pub sort: Sort,
+    pub group: Group,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            artist: None,
+            general: true,
+            mature: true,
+            adult: true,
+            category: None,
+            faved: Faved::Any,
+            sort: Sort::Oldest,
+            group: Group::Nothing,
+        }
+    }
+}
+
+fn compare_artists(a: &Submission, b: &Submission) -> Ordering {
+    let a = a.artist().name().to_lowercase();
+    let b = b.artist().name().to_lowercase();
+    a.cmp(&b)
+}
+
+/// Order days, with any that aren't known yet last.
+fn compare_days(a: Option<&Details>, b: Option<&Details>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.day.cmp(&b.day),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+impl Query {
+    /// Whether matching, ordering, or grouping depends on the [`Details`]
+    /// of submissions.
+    pub fn needs_details(&self) -> bool {
+        self.category.is_some()
+            || self.faved != Faved::Any
+            || self.group == Group::Date
+    }
+
+    /// Whether `submission` belongs in the list.
+    ///
+    /// Submissions whose `details` aren't known yet don't match when the
+    /// query is about them.
+    pub fn matches(
+        &self,
+        submission: &Submission,
+        details: Option<&Details>,
+    ) -> bool {
+        let allowed = match submission.rating() {
+            Rating::General => self.general,
+            Rating::Mature => self.mature,
+            Rating::Adult => self.adult,
+        };
+
+        if !allowed {
+            return false;
+        }
+
+        let artist = submission.artist().name();
+
+        if let Some(ref only) = self.artist {
+            if !only.eq_ignore_ascii_case(artist) {
+                return false;
+            }
+        }
+
+        if self.category.is_some() || self.faved != Faved::Any {
+            let details = match details {
+                Some(d) => d,
+                None => return false,
+            };
+
+            if let Some(ref category) = self.category {
+                if details.category != *category {
+                    return false;
+                }
+            }
+
+            if !self.faved.allows(details.faved) {
+                return false;
+            }
+        }
+
+        self.text.is_empty()
+            || submission.title().to_lowercase().contains(&self.text)
+            || artist.to_lowercase().contains(&self.text)
+    }
+
+    /// Order submissions, keeping groups together.
+    pub fn compare(
+        &self,
+        (a, a_details): (&Submission, Option<&Details>),
+        (b, b_details): (&Submission, Option<&Details>),
+    ) -> Ordering {
+        let grouped = match self.group {
+            Group::Nothing => Ordering::Equal,
+            Group::Artist => compare_artists(a, b),
+
+            // Days run the same way as the submissions in them.
+            Group::Date if self.sort == Sort::Newest => {
+                compare_days(b_details, a_details)
+            }
+            Group::Date => compare_days(a_details, b_details),
+        };
+
+        grouped.then_with(|| match self.sort {
+            Sort::Oldest => {
+                ViewKey::from(a).view_id.cmp(&ViewKey::from(b).view_id)
+            }
+            Sort::Newest => {
+                ViewKey::from(b).view_id.cmp(&ViewKey::from(a).view_id)
+            }
+            Sort::Title => {
+                let a = a.title().to_lowercase();
+                let b = b.title().to_lowercase();
+                a.cmp(&b)
+            }
+            Sort::Artist => compare_artists(a, b),
+        })
+    }
+
+    /// The header to show above `submission` when it comes after
+    /// `previous`, if it starts a new group.
+    pub fn header(
+        &self,
+        (submission, details): (&Submission, Option<&Details>),
+        previous: Option<(&Submission, Option<&Details>)>,
+    ) -> Option<String> {
+        match self.group {
+            Group::Nothing => None,
+            Group::Artist => {
+                let same = previous
+                    .map(|(p, _)| {
+                        compare_artists(p, submission) == Ordering::Equal
+                    })
+                    .unwrap_or(false);
+
+                if same {
+                    None
+                } else {
+                    Some(submission.artist().name().to_owned())
+                }
+            }
+            Group::Date => {
+                let same = previous
+                    .map(|(_, p)| compare_days(p, details) == Ordering::Equal)
+                    .unwrap_or(false);
+
+                if same {
+                    None
+                } else {
+                    let day = details.map(|d| d.day.clone());
+                    Some(day.unwrap_or_else(|| gettext("Date Unknown")))
+                }
+            }
+        }
+    }
+}
+
+/// A search bar with toggles for ratings, and choices of category,
+/// favorites, order and grouping.
+#[derive(Debug)]
+pub struct Search {
+    bar: gtk::SearchBar,
+    entry: gtk::SearchEntry,
+    general: gtk::ToggleButton,
+    mature: gtk::ToggleButton,
+    adult: gtk::ToggleButton,
+    sort: gtk::ComboBoxText,
+    group: gtk::ComboBoxText,
+    faved: gtk::ComboBoxText,
+
+    /// Categories by id, with an empty id for all of them.
+    category: gtk::ComboBoxText,
+    categories: RefCell<Vec<String>>,
+
+    /// Shows the artist being filtered to, and clears it when clicked.
+    artist_btn: gtk::Button,
+    artist_label: gtk::Label,
+    artist: Rc<RefCell<Option<String>>>,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        let entry = gtk::SearchEntryBuilder::new().hexpand(true).build();
+
+        let toggle = |label: &str| {
+            gtk::ToggleButtonBuilder::new()
+                .label(label)
+                .active(true)
+                .build()
+        };
+
+        let general = toggle(&gettext("General"));
+        let mature = toggle(&gettext("Mature"));
+        let adult = toggle(&gettext("Adult"));
+
+        let ratings = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        ratings.add_css_class("linked");
+        ratings.append(&general);
+        ratings.append(&mature);
+        ratings.append(&adult);
+
+        let sort = gtk::ComboBoxText::new();
+        for s in Sort::ALL {
+            sort.append_text(&s.label());
+        }
+        sort.set_active(Some(0));
+
+        let group = gtk::ComboBoxText::new();
+        for g in Group::ALL {
+            group.append_text(&g.label());
+        }
+        group.set_active(Some(0));
+
+        let faved = gtk::ComboBoxText::new();
+        for f in Faved::ALL {
+            faved.append_text(&f.label());
+        }
+        faved.set_active(Some(0));
+
+        // Filled in as submissions' categories become known.
+        let category = gtk::ComboBoxText::new();
+        category.append(Some(""), &gettext("Any Category"));
+        category.set_active_id(Some(""));
+
+        let artist_label = gtk::Label::new(None);
+        let close = gtk::Image::from_icon_name(Some("window-close-symbolic"));
+        let artist_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        artist_box.append(&artist_label);
+        artist_box.append(&close);
+
+        let artist_btn = gtk::ButtonBuilder::new()
+            .child(&artist_box)
+            .tooltip_text(&gettext("Show every artist"))
+            .visible(false)
+            .build();
+
+        // Wraps onto more lines when the inbox is narrow.
+        let chips = gtk::FlowBoxBuilder::new()
+            .selection_mode(gtk::SelectionMode::None)
+            .column_spacing(6)
+            .row_spacing(6)
+            .build();
+        chips.insert(&ratings, -1);
+        chips.insert(&artist_btn, -1);
+        chips.insert(&category, -1);
+        chips.insert(&faved, -1);
+        chips.insert(&sort, -1);
+        chips.insert(&group, -1);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        content.append(&entry);
+        content.append(&chips);
+
+        let bar = gtk::SearchBarBuilder::new()
+            .child(&content)
+            .show_close_button(true)
+            .build();
+        bar.connect_entry(&entry);
+
+        let artist: Rc<RefCell<Option<String>>> = Default::default();
+
+        let artist_clone = artist.clone();
+        artist_btn.connect_clicked(move |btn| {
+            artist_clone.replace(None);
+            btn.hide();
+        });
+
+        Self {
+            bar,
+            entry,
+            general,
+            mature,
+            adult,
+            sort,
+            group,
+            faved,
+            category,
+            categories: Default::default(),
+            artist_btn,
+            artist_label,
+            artist,
+        }
+    }
+
+    pub fn widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.bar
+    }
+
+    /// Show the search bar if it's hidden, or hide it if it's shown.
+    pub fn toggle(&self) {
+        let show = !self.bar.is_search_mode();
+        self.bar.set_search_mode(show);
+
+        if show {
+            self.entry.grab_focus();
+        }
+    }
+
+    /// Only show submissions by `artist`.
+    ///
+    /// Doesn't call the functions given to `connect_changed`.
+    pub fn set_artist(&self, artist: Option<String>) {
+        let label = artist
+            .as_deref()
+            .map(|a| gettext("Only {}").replacen("{}", a, 1));
+
+        self.artist_label.set_text(label.as_deref().unwrap_or(""));
+        self.artist_btn.set_visible(artist.is_some());
+
+        if self.artist.replace(artist).is_none() {
+            self.bar.set_search_mode(true);
+        }
+    }
+
+    /// Offer `category` as a choice, if it isn't one already.
+    ///
+    /// Doesn't call the functions given to `connect_changed`.
+    pub fn add_category(&self, category: &str) {
+        let mut categories = self.categories.borrow_mut();
+        if category.is_empty() || categories.iter().any(|c| c == category) {
+            return;
+        }
+
+        categories.push(category.to_owned());
+        self.category.append(Some(category), category);
+    }
+
+    pub fn query(&self) -> Query {
+        let text = self.entry.text().trim().to_lowercase();
+
+        let sort = self
+            .sort
+            .active()
+            .and_then(|i| Sort::ALL.get(i as usize))
+            .copied()
+            .unwrap_or(Sort::Oldest);
+
+        let group = self
+            .group
+            .active()
+            .and_then(|i| Group::ALL.get(i as usize))
+            .copied()
+            .unwrap_or(Group::Nothing);
+
+        let faved = self
+            .faved
+            .active()
+            .and_then(|i| Faved::ALL.get(i as usize))
+            .copied()
+            .unwrap_or(Faved::Any);
+
+        let category = self
+            .category
+            .active_id()
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string());
+
+        Query {
+            text,
+            artist: self.artist.borrow().clone(),
+            general: self.general.is_active(),
+            mature: self.mature.is_active(),
+            adult: self.adult.is_active(),
+            category,
+            faved,
+            sort,
+            group,
+        }
+    }
+
+    /// Call `func` whenever the query changes from the search bar.
+    pub fn connect_changed<F>(&self, func: F)
+    where
+        F: 'static + Fn(),
+    {
+        let func = Rc::new(func);
+
+        let f = func.clone();
+        self.entry.connect_search_changed(move |_| f());
+
+        for toggle in &[&self.general, &self.mature, &self.adult] {
+            let f = func.clone();
+            toggle.connect_toggled(move |_| f());
+        }
+
+        for combo in &[&self.sort, &self.group, &self.faved, &self.category] {
+            let f = func.clone();
+            combo.connect_changed(move |_| f());
+        }
+
+        self.artist_btn.connect_clicked(move |_| func());
+    }
+}
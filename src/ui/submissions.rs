@@ -1,25 +1,54 @@
 use crate::bridge::errors::RequestError;
+use crate::link;
 use crate::ptr::{Owned, Weak, Wrap};
-use crate::util::Util;
+use crate::settings::{
+    Blocklist, Collapse, Content, Layout, Settings, ThumbnailSize, Visibility,
+};
+use crate::util::{Tasks, Util};
 use crate::widgets::{PageListView, FillImage};
 
+use gettextrs::gettext;
+
 use gio::prelude::*;
 
+use glib::{StaticVariantType, ToVariant};
+
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
 use labrat::keys::{SubmissionsKey, ViewKey};
-use labrat::resources::{PreviewSize, Submission};
+use labrat::resources::Submission;
 
 use once_cell::unsync::OnceCell;
 
-use std::cell::{Cell, RefCell};
+use std::cell::{Cell, Ref, RefCell};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::search::{Details, Group, Query, Search};
 
 mod imp_item {
     use super::*;
 
     #[derive(Debug, Default, Clone)]
-    pub struct ListSubmission(pub OnceCell<Submission>);
+    pub struct ListSubmission {
+        pub submission: OnceCell<Submission>,
+
+        /// The submission this one is collapsed under, if any.
+        pub leader: RefCell<glib::WeakRef<super::ListSubmission>>,
+
+        /// Submissions collapsed under this one.
+        pub members: RefCell<Vec<super::ListSubmission>>,
+
+        pub details: RefCell<Option<Details>>,
+
+        /// Whether the submission's page has been asked for, to fill in
+        /// `details`.
+        pub details_requested: Cell<bool>,
+    }
 
     #[glib::object_subclass]
     impl ObjectSubclass for ListSubmission {
@@ -39,23 +68,138 @@ impl ListSubmission {
     pub fn new(submission: Submission) -> Self {
         let new: Self = glib::Object::new(&[]).unwrap();
         let instance: &_ = imp_item::ListSubmission::from_instance(&new);
-        instance.0.set(submission).unwrap();
+        instance.submission.set(submission).unwrap();
         new
     }
 
     pub fn submission(&self) -> &Submission {
         imp_item::ListSubmission::from_instance(self)
-            .0
+            .submission
             .get()
             .unwrap()
     }
+
+    pub fn leader(&self) -> Option<ViewKey> {
+        let leader = self.leader_item()?;
+        Some(ViewKey::from(leader.submission()))
+    }
+
+    fn leader_item(&self) -> Option<ListSubmission> {
+        let inner = imp_item::ListSubmission::from_instance(self);
+        inner.leader.borrow().upgrade()
+    }
+
+    pub fn members(&self) -> Vec<ListSubmission> {
+        let inner = imp_item::ListSubmission::from_instance(self);
+        inner.members.borrow().clone()
+    }
+
+    /// What's known from the submission's own page, once it's fetched.
+    pub fn details(&self) -> Ref<'_, Option<Details>> {
+        let inner = imp_item::ListSubmission::from_instance(self);
+        inner.details.borrow()
+    }
+
+    fn set_details(&self, details: Details) {
+        let inner = imp_item::ListSubmission::from_instance(self);
+        inner.details.replace(Some(details));
+    }
+
+    /// Whether the submission's page still has to be asked for, which it
+    /// doesn't after the first call.
+    fn request_details(&self) -> bool {
+        let inner = imp_item::ListSubmission::from_instance(self);
+        !inner.details_requested.replace(true)
+    }
+
+    /// Forget which group this submission was in.
+    fn ungroup(&self) {
+        let inner = imp_item::ListSubmission::from_instance(self);
+        inner.leader.replace(glib::WeakRef::new());
+        inner.members.borrow_mut().clear();
+    }
+
+    /// Collapse `member` under this submission.
+    fn add_member(&self, member: &ListSubmission) {
+        let member_inner = imp_item::ListSubmission::from_instance(member);
+        member_inner.leader.replace(self.downgrade());
+
+        let inner = imp_item::ListSubmission::from_instance(self);
+        inner.members.borrow_mut().push(member.clone());
+    }
 }
 
 #[derive(Debug)]
 struct SubmissionListItemWidgets {
     box_: gtk::Box,
+    header: gtk::Label,
     thumbnail: FillImage,
     avatar: adw::Avatar,
+    menu: gtk::PopoverMenu,
+    group: GroupWidgets,
+}
+
+/// Something to do to a group, given its leader.
+type GroupAction = fn(&Wrap<Submissions>, ViewKey);
+
+/// What a submission shows when others are collapsed under it.
+#[derive(Debug)]
+struct GroupWidgets {
+    box_: gtk::Box,
+
+    /// Thumbnails of some of the collapsed submissions.
+    strip: gtk::Box,
+
+    expand_btn: gtk::ToggleButton,
+    open_all_btn: gtk::Button,
+    fav_all_btn: gtk::Button,
+    remove_all_btn: gtk::Button,
+}
+
+impl GroupWidgets {
+    fn new() -> Self {
+        let strip = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+
+        let expand_btn = gtk::ToggleButton::new();
+
+        let button = |icon: &str, tooltip: &str| {
+            gtk::ButtonBuilder::new()
+                .icon_name(icon)
+                .tooltip_text(tooltip)
+                .build()
+        };
+
+        let open_all_btn =
+            button("web-browser-symbolic", &gettext("Open All in Browser"));
+        let fav_all_btn =
+            button("starred-symbolic", &gettext("Add All to Favorites"));
+        let remove_all_btn =
+            button("edit-delete-symbolic", &gettext("Remove All from Inbox"));
+
+        let actions = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        actions.append(&expand_btn);
+        actions.append(&open_all_btn);
+        actions.append(&fav_all_btn);
+        actions.append(&remove_all_btn);
+
+        let box_ = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .margin_top(6)
+            .visible(false)
+            .build();
+        box_.append(&strip);
+        box_.append(&actions);
+
+        Self {
+            box_,
+            strip,
+            expand_btn,
+            open_all_btn,
+            fav_all_btn,
+            remove_all_btn,
+        }
+    }
 }
 
 mod imp_widget {
@@ -66,8 +210,22 @@ mod imp_widget {
         widgets: SubmissionListItemWidgets,
         submission: RefCell<Option<Submission>>,
         weak: RefCell<Weak<Submissions>>,
+        tasks: Tasks,
+
+        /// Size of the thumbnail being shown.
+        size: Cell<Option<ThumbnailSize>>,
+
+        /// How the submission's rating said to show it when it was bound.
+        visibility: Cell<Option<Visibility>>,
+
+        /// Submissions collapsed under this one, as last shown.
+        members: RefCell<Vec<ViewKey>>,
+        strip_tasks: Tasks,
     }
 
+    /// Most thumbnails to show of the submissions in a group.
+    const STRIP_SIZE: usize = 5;
+
     impl Default for SubmissionListItem {
         fn default() -> Self {
             let thumbnail = FillImage::new();
@@ -76,22 +234,46 @@ mod imp_widget {
 
             let avatar = adw::Avatar::new(32, None, false);
 
+            let header = gtk::LabelBuilder::new()
+                .xalign(0.)
+                .margin_top(12)
+                .margin_bottom(6)
+                .visible(false)
+                .build();
+            header.add_css_class("heading");
+
+            let group = GroupWidgets::new();
+
             let box_ = gtk::Box::new(gtk::Orientation::Vertical, 0);
+            box_.append(&header);
             box_.append(&avatar);
             box_.append(&thumbnail);
+            box_.append(&group.box_);
 
             thumbnail.show();
 
+            let menu =
+                gtk::PopoverMenu::from_model(Option::<&gio::MenuModel>::None);
+            menu.set_has_arrow(false);
+
             let widgets = SubmissionListItemWidgets {
                 box_,
+                header,
                 thumbnail,
                 avatar,
+                menu,
+                group,
             };
 
             Self {
                 widgets,
                 submission: Default::default(),
                 weak: RefCell::new(Weak::new()),
+                tasks: Tasks::default(),
+                size: Cell::new(None),
+                visibility: Cell::new(None),
+                members: Default::default(),
+                strip_tasks: Tasks::default(),
             }
         }
     }
@@ -101,6 +283,148 @@ mod imp_widget {
             self.weak.replace(weak);
         }
 
+        /// Show the submissions collapsed under this one, if any.
+        pub fn set_group(&self, members: &[Submission], expanded: bool) {
+            let group = &self.widgets.group;
+            group.box_.set_visible(!members.is_empty());
+
+            // Only reaches the list if it's different.
+            group.expand_btn.set_active(expanded);
+
+            let label = if expanded {
+                gettext("Hide {}")
+            } else {
+                gettext("{} More")
+            };
+            let label = label.replacen("{}", &members.len().to_string(), 1);
+            group.expand_btn.set_label(&label);
+
+            let keys: Vec<_> = members.iter().map(ViewKey::from).collect();
+            if *self.members.borrow() == keys {
+                return;
+            }
+
+            self.members.replace(keys);
+            self.strip_tasks.cancel_all();
+
+            while let Some(child) = group.strip.first_child() {
+                group.strip.remove(&child);
+            }
+
+            let parent = match self.weak.borrow().upgrade() {
+                Some(p) => p,
+                None => return,
+            };
+
+            // Anything that would be blurred is left out rather than shown
+            // too small to reveal.
+            let content = parent.content.borrow();
+            let shown = members
+                .iter()
+                .filter(|m| content.visibility(m.rating()) == Visibility::Show)
+                .take(STRIP_SIZE);
+
+            for member in shown {
+                let image = FillImage::new();
+                image.set_size_request(48, 48);
+                group.strip.append(&image);
+
+                let thumb = member.preview(ThumbnailSize::Small.preview_size());
+                let util = parent.util.clone();
+                let task = parent.util.spawn_local(async move {
+                    // A missing thumbnail just leaves a gap in the strip.
+                    match util.fetch_pixbuf(thumb.as_str()).await {
+                        Ok(pixbuf) => image.set_pixbuf(pixbuf),
+                        Err(e) => log::warn!("unable to fetch strip: {}", e),
+                    }
+
+                    Result::<_, Infallible>::Ok(())
+                });
+                self.strip_tasks.push(task);
+            }
+        }
+
+        /// Call `func` with the list and this submission, as the leader of
+        /// its group.
+        fn with_group(&self, func: GroupAction) {
+            let parent = match self.weak.borrow().upgrade() {
+                Some(p) => p,
+                None => return,
+            };
+
+            let key = match self.submission.borrow().as_ref() {
+                Some(s) => ViewKey::from(s),
+                None => return,
+            };
+
+            func(&parent, key);
+        }
+
+        pub fn set_header(&self, header: Option<&str>) {
+            let label = &self.widgets.header;
+            label.set_text(header.unwrap_or(""));
+            label.set_visible(header.is_some());
+        }
+
+        pub fn set_marked(&self, marked: bool) {
+            if marked {
+                self.widgets.box_.add_css_class("dim-label");
+            } else {
+                self.widgets.box_.remove_css_class("dim-label");
+            }
+        }
+
+        /// Show the context menu for the submission, pointing at `x, y`.
+        fn popup_menu(&self, x: f64, y: f64) -> bool {
+            let parent = match self.weak.borrow().upgrade() {
+                Some(p) => p,
+                None => return false,
+            };
+
+            let artist = match self.submission.borrow().as_ref() {
+                Some(s) => s.artist().name().to_owned(),
+                None => return false,
+            };
+
+            let blocked = parent.blocklist.borrow().blocks_artist(&artist);
+
+            let (label, action) = if blocked {
+                (gettext("Unblock {}"), Submissions::UNBLOCK_ARTIST)
+            } else {
+                (gettext("Block {}"), Submissions::BLOCK_ARTIST)
+            };
+
+            let label = label.replacen("{}", &artist, 1);
+            let item = gio::MenuItem::new(Some(&label), None);
+            item.set_action_and_target_value(
+                Some(action),
+                Some(&artist.to_variant()),
+            );
+
+            let model = gio::Menu::new();
+            model.append_item(&item);
+
+            let label = gettext("Show Only {}").replacen("{}", &artist, 1);
+            let item = gio::MenuItem::new(Some(&label), None);
+            item.set_action_and_target_value(
+                Some(Submissions::FILTER_ARTIST),
+                Some(&artist.to_variant()),
+            );
+            model.append_item(&item);
+
+            let menu = &self.widgets.menu;
+            menu.set_menu_model(Some(&model));
+            menu.set_pointing_to(&gdk::Rectangle {
+                x: x as i32,
+                y: y as i32,
+                width: 1,
+                height: 1,
+            });
+            menu.popup();
+
+            true
+        }
+
         fn same_submission(
             sub: &Submission,
             inst: &glib::WeakRef<super::SubmissionListItem>,
@@ -118,7 +442,38 @@ mod imp_widget {
             None
         }
 
+        fn thumbnail_size(&self) -> Option<ThumbnailSize> {
+            let parent = self.weak.borrow().upgrade()?;
+            let size = parent.util.settings().get().inbox.thumbnail_size;
+            Some(size)
+        }
+
+        fn visibility(&self, submission: &Submission) -> Option<Visibility> {
+            let parent = self.weak.borrow().upgrade()?;
+            let visibility =
+                parent.content.borrow().visibility(submission.rating());
+            Some(visibility)
+        }
+
+        /// Blur the thumbnail if its rating calls for it.
+        ///
+        /// Unless `force`d, only done when the setting changes, so a
+        /// thumbnail that was revealed stays that way while the row is
+        /// rebound.
+        fn apply_visibility(&self, submission: &Submission, force: bool) {
+            let visibility = self.visibility(submission);
+            if self.visibility.replace(visibility) == visibility && !force {
+                return;
+            }
+
+            let blurred = visibility == Some(Visibility::Blur);
+            self.widgets.thumbnail.set_blurred(blurred);
+        }
+
         fn update(&self, submission: Submission) {
+            // Images for the previous submission are no longer needed.
+            self.tasks.cancel_all();
+
             self.widgets
                 .avatar
                 .set_text(Some(submission.artist().name()));
@@ -130,8 +485,14 @@ mod imp_widget {
 
                 let util = parent.util.clone();
                 let sub0 = submission.clone();
-                parent.util.spawn_local(async move {
-                    let pixbuf = util.fetch_pixbuf(&avatar_uri).await?;
+                let task = parent.util.spawn_local(async move {
+                    let pixbuf = match util.fetch_pixbuf(&avatar_uri).await {
+                        Ok(p) => p,
+                        Err(e) => {
+                            log::warn!("unable to load {}: {}", avatar_uri, e);
+                            return Ok(());
+                        }
+                    };
 
                     if let Some(inst) = Self::same_submission(&sub0, &inst) {
                         let this = Self::from_instance(&inst);
@@ -140,15 +501,25 @@ mod imp_widget {
                         ));
                     }
 
-                    Result::<_, glib::Error>::Ok(())
+                    Result::<_, Infallible>::Ok(())
                 });
+                self.tasks.push(task);
 
                 // Fetch the thumbnail.
+                let size = parent.util.settings().get().inbox.thumbnail_size;
+                self.size.set(Some(size));
+
                 let inst = self.instance().downgrade();
                 let util = parent.util.clone();
-                parent.util.spawn_local(async move {
-                    let thumb = submission.preview(PreviewSize::Xl);
-                    let pixbuf = util.fetch_pixbuf(thumb.as_str()).await?;
+                let task = parent.util.spawn_local(async move {
+                    let thumb = submission.preview(size.preview_size());
+                    let pixbuf = match util.fetch_pixbuf(thumb.as_str()).await {
+                        Ok(p) => p,
+                        Err(e) => {
+                            log::warn!("unable to load {}: {}", thumb, e);
+                            return Ok(());
+                        }
+                    };
 
                     if let Some(inst) =
                         Self::same_submission(&submission, &inst)
@@ -158,8 +529,9 @@ mod imp_widget {
                         this.widgets.thumbnail.set_pixbuf(pixbuf);
                     }
 
-                    Result::<_, glib::Error>::Ok(())
+                    Result::<_, Infallible>::Ok(())
                 });
+                self.tasks.push(task);
             }
         }
 
@@ -168,15 +540,30 @@ mod imp_widget {
 
             match (old, submission) {
                 (None, None) => (),
-                (Some(_), None) => self.widgets.thumbnail.clear(),
-                (None, Some(s)) => self.update(s),
+                (Some(_), None) => {
+                    self.tasks.cancel_all();
+                    self.widgets.thumbnail.clear();
+                }
+                (None, Some(s)) => {
+                    self.apply_visibility(&s, true);
+                    self.update(s);
+                }
 
                 (Some(old), Some(new)) => {
                     let old_key = ViewKey::from(&old);
                     let new_key = ViewKey::from(&new);
 
                     if old_key != new_key {
+                        self.apply_visibility(&new, true);
                         self.update(new);
+                    } else {
+                        // Bound again after the content settings changed.
+                        self.apply_visibility(&new, false);
+
+                        if self.size.get() != self.thumbnail_size() {
+                            // Bound again after the size setting changed.
+                            self.update(new);
+                        }
                     }
                 }
             }
@@ -194,10 +581,63 @@ mod imp_widget {
         fn constructed(&self, obj: &Self::Type) {
             self.parent_constructed(obj);
             self.widgets.box_.set_parent(obj);
+            self.widgets.menu.set_parent(obj);
+
+            let click = gtk::GestureClick::new();
+            click.set_button(gdk::BUTTON_SECONDARY);
+
+            let weak = obj.downgrade();
+            click.connect_pressed(move |gesture, _, x, y| {
+                let obj = match weak.upgrade() {
+                    Some(o) => o,
+                    None => return,
+                };
+
+                if Self::from_instance(&obj).popup_menu(x, y) {
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                }
+            });
+
+            obj.add_controller(&click);
+
+            let group = &self.widgets.group;
+            let buttons: [(&gtk::Button, GroupAction); 3] = [
+                (&group.open_all_btn, |p, k| p.open_group(&k)),
+                (&group.fav_all_btn, |p, k| p.fav_group(&k)),
+                (&group.remove_all_btn, |p, k| p.remove_group(&k)),
+            ];
+
+            for &(button, func) in buttons.iter() {
+                let weak = obj.downgrade();
+                button.connect_clicked(move |_| {
+                    if let Some(obj) = weak.upgrade() {
+                        Self::from_instance(&obj).with_group(func);
+                    }
+                });
+            }
+
+            let weak = obj.downgrade();
+            group.expand_btn.connect_toggled(move |btn| {
+                let obj = match weak.upgrade() {
+                    Some(o) => o,
+                    None => return,
+                };
+
+                let func: GroupAction = if btn.is_active() {
+                    |p, k| p.set_expanded(k, true)
+                } else {
+                    |p, k| p.set_expanded(k, false)
+                };
+
+                Self::from_instance(&obj).with_group(func);
+            });
         }
 
         fn dispose(&self, _: &Self::Type) {
+            self.tasks.cancel_all();
+            self.strip_tasks.cancel_all();
             self.widgets.box_.unparent();
+            self.widgets.menu.unparent();
         }
     }
 
@@ -214,6 +654,7 @@ mod imp_widget {
             baseline: i32,
         ) {
             self.widgets.box_.allocate(width, height, baseline, None);
+            self.widgets.menu.present();
         }
 
         fn measure(
@@ -262,30 +703,230 @@ impl SubmissionListItem {
         let inner = imp_widget::SubmissionListItem::from_instance(self);
         inner.set_weak(weak);
     }
+
+    /// Show the submissions collapsed under this one, if any.
+    pub fn set_group(&self, members: &[Submission], expanded: bool) {
+        let inner = imp_widget::SubmissionListItem::from_instance(self);
+        inner.set_group(members, expanded);
+    }
+
+    /// Show a header above the submission, when it starts a group.
+    pub fn set_header(&self, header: Option<&str>) {
+        let inner = imp_widget::SubmissionListItem::from_instance(self);
+        inner.set_header(header);
+    }
+
+    /// Show whether the submission is marked for removal from the inbox.
+    pub fn set_marked(&self, marked: bool) {
+        let inner = imp_widget::SubmissionListItem::from_instance(self);
+        inner.set_marked(marked);
+    }
+}
+
+/// What a row shows besides the submission itself, to tell when it has to
+/// be bound again.
+#[derive(Debug, PartialEq)]
+struct Bound {
+    header: Option<String>,
+    members: Vec<ViewKey>,
+}
+
+/// Order `a` and `b` the way `query` says, except that submissions
+/// collapsed under a leader come right after it, and before the next one.
+fn compare_items(
+    query: &Query,
+    a: &ListSubmission,
+    b: &ListSubmission,
+) -> Ordering {
+    let compare = |a: &ListSubmission, b: &ListSubmission| {
+        let (a_details, b_details) = (a.details(), b.details());
+        query.compare(
+            (a.submission(), a_details.as_ref()),
+            (b.submission(), b_details.as_ref()),
+        )
+    };
+
+    let a_leader = a.leader_item().unwrap_or_else(|| a.clone());
+    let b_leader = b.leader_item().unwrap_or_else(|| b.clone());
+
+    if a_leader != b_leader {
+        // Leaders that tie would let their members mix.
+        let a_key = ViewKey::from(a_leader.submission());
+        let b_key = ViewKey::from(b_leader.submission());
+        return compare(&a_leader, &b_leader)
+            .then_with(|| a_key.view_id.cmp(&b_key.view_id));
+    }
+
+    match (*a == a_leader, *b == b_leader) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        _ => compare(a, b),
+    }
 }
 
 #[derive(Debug)]
 pub struct Submissions {
     page_list_view: Owned<PageListView<ListSubmission>>,
     scrolled_window: gtk::ScrolledWindow,
+    box_: gtk::Box,
     util: Util,
     fetching: Cell<bool>,
+    tasks: Tasks,
+
+    /// The page to fetch when the list needs more, if there are any left.
+    next: RefCell<Option<SubmissionsKey>>,
+
+    /// Submissions to remove from the inbox together.
+    marked: RefCell<Vec<ViewKey>>,
+
+    filter: gtk::CustomFilter,
+
+    /// Shows how many submissions the blocklist hides, and shows them
+    /// while it's active.
+    hidden_btn: gtk::ToggleButton,
+
+    /// Shows why the last page couldn't be fetched, with a way to try
+    /// again.
+    error_row: gtk::Box,
+    error_label: gtk::Label,
+
+    search: Search,
+    sorter: gtk::CustomSorter,
+
+    /// What `search` asked for when it last changed.
+    query: RefCell<Query>,
+
+    /// Leaders of groups showing the submissions collapsed under them.
+    expanded: RefCell<Vec<ViewKey>>,
+
+    /// Whether groups and headers will be brought up to date soon.
+    refreshing: Cell<bool>,
+
+    /// Whether details have come in since the list was last sorted.
+    details_changed: Cell<bool>,
+
+    /// What each bound row was bound with.
+    bound: RefCell<Vec<(ViewKey, Bound)>>,
+
+    /// Settings the filter reads for every item, kept here rather than
+    /// copied out of the store each time.
+    content: RefCell<Content>,
+    blocklist: RefCell<Blocklist>,
 }
 
 impl Submissions {
+    pub const NEXT: &'static str = "inbox.next";
+    pub const PREVIOUS: &'static str = "inbox.previous";
+    pub const OPEN: &'static str = "inbox.open";
+    pub const FAV: &'static str = "inbox.fav";
+    pub const MARK: &'static str = "inbox.mark";
+    pub const REMOVE_MARKED: &'static str = "inbox.remove-marked";
+    pub const OPEN_IN_BROWSER: &'static str = "inbox.open-in-browser";
+    pub const BLOCK_ARTIST: &'static str = "inbox.block-artist";
+    pub const UNBLOCK_ARTIST: &'static str = "inbox.unblock-artist";
+    pub const FILTER_ARTIST: &'static str = "inbox.filter-artist";
+    pub const SEARCH: &'static str = "inbox.search";
+
     pub(crate) fn new(util: Util) -> Owned<Self> {
         let factory = gtk::SignalListItemFactory::new();
 
-        let page_list_view = PageListView::new(&factory, |plv| todo!());
+        // The list asks for more before there's an inbox to ask.
+        let parent = Rc::new(RefCell::new(Weak::<Self>::new()));
+
+        let parent_clone = parent.clone();
+        let page_list_view = PageListView::new(&factory, move |_| {
+            if let Some(this) = parent_clone.borrow().upgrade() {
+                this.fetch();
+            }
+        });
 
-        let scrolled_window = gtk::ScrolledWindow::new();
+        let scrolled_window =
+            gtk::ScrolledWindowBuilder::new().vexpand(true).build();
         scrolled_window.set_child(Some(page_list_view.widget()));
 
+        let hidden_btn = gtk::ToggleButtonBuilder::new()
+            .tooltip_text(&gettext("Show blocked submissions"))
+            .visible(false)
+            .build();
+        hidden_btn.add_css_class("flat");
+
+        let search = Search::new();
+
+        let error_label = gtk::LabelBuilder::new()
+            .wrap(true)
+            .xalign(0.)
+            .hexpand(true)
+            .build();
+        let retry_btn = gtk::Button::with_label(&gettext("Try Again"));
+
+        let error_row = gtk::BoxBuilder::new()
+            .spacing(12)
+            .margin_top(6)
+            .margin_bottom(6)
+            .margin_start(12)
+            .margin_end(12)
+            .visible(false)
+            .build();
+        error_row.append(&error_label);
+        error_row.append(&retry_btn);
+
+        let box_ = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        box_.append(search.widget());
+        box_.append(&hidden_btn);
+        box_.append(&scrolled_window);
+        box_.append(&error_row);
+
         let owned = Owned::new(Self {
             util,
             page_list_view,
             scrolled_window,
+            box_,
             fetching: Cell::new(false),
+            tasks: Tasks::default(),
+            next: RefCell::new(Some(SubmissionsKey::oldest())),
+            marked: Default::default(),
+            filter: gtk::CustomFilter::new(|_| true),
+            hidden_btn,
+            error_row,
+            error_label,
+            search,
+            sorter: gtk::CustomSorter::new(|_, _| gtk::Ordering::Equal),
+            query: Default::default(),
+            expanded: Default::default(),
+            refreshing: Cell::new(false),
+            details_changed: Cell::new(false),
+            bound: Default::default(),
+            content: Default::default(),
+            blocklist: Default::default(),
+        });
+
+        parent.replace(Owned::downgrade(&owned));
+
+        let weak = Owned::downgrade(&owned);
+        retry_btn.connect_clicked(move |_| {
+            if let Some(this) = weak.upgrade() {
+                this.fetch();
+            }
+        });
+
+        owned.add_actions();
+        owned.watch_settings();
+        owned.watch_search();
+
+        let weak = Owned::downgrade(&owned);
+        owned.hidden_btn.connect_toggled(move |_| {
+            if let Some(this) = weak.upgrade() {
+                this.filter.changed(gtk::FilterChange::Different);
+                this.update_hidden();
+            }
+        });
+
+        let weak = Owned::downgrade(&owned);
+        owned.page_list_view.connect_items_changed(move || {
+            if let Some(this) = weak.upgrade() {
+                this.update_hidden();
+                this.schedule_refresh();
+            }
         });
 
         let weak = Owned::downgrade(&owned);
@@ -296,19 +937,48 @@ impl Submissions {
             item.set_child(Some(&child));
         });
 
-        factory.connect_bind(|_, list_item| {
+        let weak = Owned::downgrade(&owned);
+        factory.connect_bind(move |_, list_item| {
             let child = list_item.child().unwrap();
             let item = list_item.item().unwrap();
             let widget: SubmissionListItem = child.downcast().unwrap();
             let sub: &ListSubmission = item.downcast_ref().unwrap();
             widget.set_submission(Some(sub.submission().clone()));
+
+            if let Some(this) = weak.upgrade() {
+                let key = ViewKey::from(sub.submission());
+                widget.set_marked(this.marked.borrow().contains(&key));
+
+                let members: Vec<_> = sub
+                    .members()
+                    .iter()
+                    .map(|m| m.submission().clone())
+                    .collect();
+                let expanded = this.expanded.borrow().contains(&key);
+                widget.set_group(&members, expanded);
+
+                let bound = this.bound_state(list_item.position(), sub);
+                widget.set_header(bound.header.as_deref());
+
+                let mut all = this.bound.borrow_mut();
+                all.retain(|(k, _)| *k != key);
+                all.push((key, bound));
+            }
         });
 
-        factory.connect_unbind(|_, item| {
-            if let Some(child) = item.child() {
-                let _widget: SubmissionListItem = child.downcast().unwrap();
-                // Rows are bound/unbound as they are selected, so clearing the
-                // binding doesn't make sense?
+        // Rows are bound/unbound as they are selected, so the widget keeps
+        // showing the submission; the list just stops keeping track of it.
+        let weak = Owned::downgrade(&owned);
+        factory.connect_unbind(move |_, list_item| {
+            let item = match list_item.item() {
+                Some(i) => i,
+                None => return,
+            };
+
+            let sub: &ListSubmission = item.downcast_ref().unwrap();
+            if let Some(this) = weak.upgrade() {
+                let key = ViewKey::from(sub.submission());
+                this.bound.borrow_mut().retain(|(k, _)| *k != key);
             }
         });
 
@@ -322,42 +992,730 @@ impl Submissions {
 
 impl Wrap<Submissions> {
     pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
-        &self.scrolled_window
+        &self.box_
+    }
+
+    /// Whether `item` belongs in the list, given the groups it's in.
+    fn is_shown(&self, item: &ListSubmission) -> bool {
+        if !self.passes(item) {
+            return false;
+        }
+
+        match item.leader() {
+            Some(leader) => self.expanded.borrow().contains(&leader),
+            None => true,
+        }
+    }
+
+    /// Whether `item` belongs in the list, given its rating, the
+    /// blocklist, and the search.
+    fn passes(&self, item: &ListSubmission) -> bool {
+        let submission = item.submission();
+        let rating = submission.rating();
+
+        if self.content.borrow().visibility(rating) == Visibility::Hide {
+            return false;
+        }
+
+        let details = item.details();
+        if !self.query.borrow().matches(submission, details.as_ref()) {
+            return false;
+        }
+
+        let tags = details.as_ref().map(|d| d.tags.as_slice());
+        self.hidden_btn.is_active()
+            || !self.blocklist.borrow().blocks(submission, tags)
+    }
+
+    /// Count the submissions the blocklist hides, hiding the count when
+    /// there aren't any.
+    fn update_hidden(&self) {
+        let blocklist = self.blocklist.borrow();
+        let hidden = self
+            .page_list_view
+            .collect_where(|item| {
+                let details = item.details();
+                let tags = details.as_ref().map(|d| d.tags.as_slice());
+                blocklist.blocks(item.submission(), tags)
+            })
+            .len() as u32;
+
+        let label = if self.hidden_btn.is_active() {
+            gettext("Hide {} Blocked")
+        } else {
+            gettext("{} Hidden")
+        };
+
+        let label = label.replacen("{}", &hidden.to_string(), 1);
+        self.hidden_btn.set_label(&label);
+        self.hidden_btn
+            .set_visible(hidden > 0 || self.hidden_btn.is_active());
+    }
+
+    fn watch_search(&self) {
+        let weak = self.weak();
+        self.sorter.set_sort_func(move |a, b| {
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return gtk::Ordering::Equal,
+            };
+
+            let a: &ListSubmission = a.downcast_ref().unwrap();
+            let b: &ListSubmission = b.downcast_ref().unwrap();
+
+            compare_items(&this.query.borrow(), a, b).into()
+        });
+        self.page_list_view.set_sorter(Some(&self.sorter));
+
+        let weak = self.weak();
+        self.search.connect_changed(move || {
+            if let Some(this) = weak.upgrade() {
+                this.apply_query();
+            }
+        });
+    }
+
+    fn apply_query(&self) {
+        let query = self.search.query();
+        if *self.query.borrow() == query {
+            return;
+        }
+
+        let old = self.query.replace(query);
+        self.regroup();
+        self.filter.changed(gtk::FilterChange::Different);
+
+        // Sorting again moves every row, so only when the order changes.
+        let reorder = {
+            let query = self.query.borrow();
+            old.sort != query.sort || old.group != query.group
+        };
+
+        if reorder {
+            self.sorter.changed(gtk::SorterChange::Different);
+        }
+
+        self.fetch_details();
+
+        // Headers depend on the item before, which may not be the same one.
+        self.rebind_changed();
+    }
+
+    /// Bring groups and headers up to date soon, if there are any that
+    /// might need to change, after items or their details come in.
+    ///
+    /// Waits a little, so adding a page of items only does it once.
+    fn schedule_refresh(&self) {
+        let collapse = self.util.settings().get().inbox.collapse;
+        let query = self.query.borrow();
+        if query.group == Group::Nothing
+            && collapse == Collapse::Off
+            && !query.needs_details()
+        {
+            return;
+        }
+
+        if self.refreshing.replace(true) {
+            return;
+        }
+
+        let weak = self.weak();
+        glib::timeout_add_local(Duration::from_millis(100), move || {
+            if let Some(this) = weak.upgrade() {
+                this.refreshing.set(false);
+                this.regroup();
+                this.filter.changed(gtk::FilterChange::Different);
+
+                if this.details_changed.replace(false) {
+                    this.sorter.changed(gtk::SorterChange::Different);
+                }
+
+                this.fetch_details();
+                this.rebind_changed();
+            }
+
+            glib::Continue(false)
+        });
+    }
+
+    /// What the row at `position` should show for `item`.
+    fn bound_state(&self, position: u32, item: &ListSubmission) -> Bound {
+        let previous = match position {
+            0 => None,
+            p => self.page_list_view.get(p - 1),
+        };
+
+        let details = item.details();
+        let previous_details = previous.as_ref().map(ListSubmission::details);
+        let previous = previous
+            .as_ref()
+            .zip(previous_details.as_ref())
+            .map(|(p, d)| (p.submission(), d.as_ref()));
+
+        let header = self
+            .query
+            .borrow()
+            .header((item.submission(), details.as_ref()), previous);
+
+        let members = item
+            .members()
+            .iter()
+            .map(|m| ViewKey::from(m.submission()))
+            .collect();
+
+        Bound { header, members }
+    }
+
+    /// Bind the rows again whose header or group has changed, leaving the
+    /// rest, and the selection, alone.
+    fn rebind_changed(&self) {
+        let mut stale = Vec::new();
+        let mut position = 0;
+
+        while let Some(item) = self.page_list_view.get(position) {
+            let key = ViewKey::from(item.submission());
+            let bound = self.bound.borrow();
+
+            // Rows that aren't bound get it right when they are.
+            if let Some((_, b)) = bound.iter().find(|(k, _)| *k == key) {
+                if *b != self.bound_state(position, &item) {
+                    stale.push(key);
+                }
+            }
+
+            position += 1;
+        }
+
+        if !stale.is_empty() {
+            self.page_list_view.refresh_where(|i| {
+                stale.contains(&ViewKey::from(i.submission()))
+            });
+        }
+    }
+
+    /// Fetch the page of each submission missing the details the search
+    /// needs, one at a time.
+    fn fetch_details(&self) {
+        if !self.query.borrow().needs_details() {
+            return;
+        }
+
+        let items: Vec<_> = self
+            .page_list_view
+            .collect_where(|i| i.details().is_none())
+            .into_iter()
+            .filter(ListSubmission::request_details)
+            .collect();
+
+        if items.is_empty() {
+            return;
+        }
+
+        let weak = self.weak();
+        let client = self.util.client().clone();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            for item in items {
+                let key = ViewKey::from(item.submission());
+                let view = match client.view(key).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        log::warn!("unable to fetch submission details: {}", e);
+                        continue;
+                    }
+                };
+
+                let this = match weak.upgrade() {
+                    Some(t) => t,
+                    None => break,
+                };
+
+                let details = Details {
+                    category: view.category().to_owned(),
+                    faved: view.faved(),
+                    day: view.posted().format("%Y-%m-%d").to_string(),
+                    tags: view.tags().to_vec(),
+                };
+
+                this.search.add_category(&details.category);
+                item.set_details(details);
+
+                this.details_changed.set(true);
+                this.schedule_refresh();
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Work out which submissions to collapse under which.
+    ///
+    /// Groups follow the order submissions were loaded in, whatever order
+    /// they're shown in.
+    fn regroup(&self) {
+        let items = self.page_list_view.collect_where(|_| true);
+        let before: Vec<_> = items.iter().map(ListSubmission::leader).collect();
+
+        for item in items.iter() {
+            item.ungroup();
+        }
+
+        let collapse = self.util.settings().get().inbox.collapse;
+        if collapse != Collapse::Off {
+            let mut leaders: HashMap<String, ListSubmission> = HashMap::new();
+            let mut previous: Option<String> = None;
+
+            for item in items.iter().filter(|i| self.passes(i)) {
+                let artist = item.submission().artist().name().to_lowercase();
+
+                // Only the latest run of each artist can be added to.
+                if collapse == Collapse::Consecutive
+                    && previous.as_ref() != Some(&artist)
+                {
+                    leaders.clear();
+                }
+
+                match leaders.get(&artist) {
+                    Some(leader) => leader.add_member(item),
+                    None => {
+                        leaders.insert(artist.clone(), item.clone());
+                    }
+                }
+
+                previous = Some(artist);
+            }
+        }
+
+        // Members are sorted after their leader, so they move with it.
+        let after = items.iter().map(ListSubmission::leader);
+        if !before.into_iter().eq(after) {
+            self.sorter.changed(gtk::SorterChange::Different);
+        }
+    }
+
+    /// Show or hide the submissions collapsed under `leader`.
+    fn set_expanded(&self, leader: ViewKey, expanded: bool) {
+        {
+            let mut list = self.expanded.borrow_mut();
+            if list.contains(&leader) == expanded {
+                return;
+            }
+
+            if expanded {
+                list.push(leader);
+            } else {
+                list.retain(|k| *k != leader);
+            }
+        }
+
+        self.filter.changed(gtk::FilterChange::Different);
+        self.rebind_changed();
+    }
+
+    /// `leader` and the submissions collapsed under it.
+    fn group_of(&self, leader: &ViewKey) -> Vec<ViewKey> {
+        let mut keys = vec![leader.clone()];
+
+        let found = self
+            .page_list_view
+            .collect_where(|i| ViewKey::from(i.submission()) == *leader);
+
+        if let Some(item) = found.first() {
+            let members = item.members();
+            keys.extend(members.iter().map(|m| ViewKey::from(m.submission())));
+        }
+
+        keys
+    }
+
+    fn open_group(&self, leader: &ViewKey) {
+        let window = self.window();
+
+        for key in self.group_of(leader) {
+            let url = link::view_url(&key);
+            gtk::show_uri(window.as_ref(), url.as_str(), gdk::CURRENT_TIME);
+        }
+    }
+
+    fn fav_group(&self, leader: &ViewKey) {
+        self.fav(self.group_of(leader));
+    }
+
+    fn remove_group(&self, leader: &ViewKey) {
+        self.expanded.borrow_mut().retain(|k| k != leader);
+        self.clear(self.group_of(leader));
+    }
+
+    fn watch_settings(&self) {
+        let weak = self.weak();
+        let filter = self.filter.clone();
+        filter.set_filter_func(move |item| {
+            let item: &ListSubmission = item.downcast_ref().unwrap();
+            match weak.upgrade() {
+                Some(this) => this.is_shown(item),
+                None => true,
+            }
+        });
+        self.page_list_view.set_filter(Some(&filter));
+
+        self.apply_settings(&self.util.settings().get());
+
+        let mut settings = self.util.settings().subscribe();
+        let weak = self.weak();
+
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let mut current = settings.borrow().clone();
+
+            while settings.changed().await.is_ok() {
+                let this = match weak.upgrade() {
+                    Some(t) => t,
+                    None => break,
+                };
+
+                let new = settings.borrow().clone();
+                this.apply_settings(&new);
+
+                if new.content != current.content
+                    || new.blocklist != current.blocklist
+                    || new.inbox.collapse != current.inbox.collapse
+                {
+                    this.regroup();
+                    filter.changed(gtk::FilterChange::Different);
+                    this.update_hidden();
+                }
+
+                // Rebinding picks up new sizes, blurring, and groups.
+                if new.inbox.thumbnail_size != current.inbox.thumbnail_size
+                    || new.inbox.collapse != current.inbox.collapse
+                    || new.content != current.content
+                {
+                    this.page_list_view.refresh_all();
+                }
+
+                current = new;
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    fn apply_settings(&self, settings: &Settings) {
+        self.content.replace(settings.content.clone());
+        self.blocklist.replace(settings.blocklist.clone());
+
+        let columns = match settings.inbox.layout {
+            Layout::List => 1,
+            Layout::Grid => 6,
+        };
+
+        self.page_list_view.set_columns(columns);
+        self.page_list_view
+            .set_end_diff(f64::from(settings.inbox.load_ahead));
+    }
+
+    /// Actions for going through the inbox from the keyboard, with
+    /// shortcuts that work while the list has focus.
+    fn add_actions(&self) {
+        let group = gio::SimpleActionGroup::new();
+        let shortcuts = gtk::ShortcutController::new();
+
+        // Ahead of the list, which would activate the focused row on Enter
+        // rather than the selected one.
+        shortcuts.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+        let actions: [(&str, &str, fn(&Self)); 8] = [
+            (Submissions::NEXT, "j", |t| t.move_cursor(true)),
+            (Submissions::PREVIOUS, "k", |t| t.move_cursor(false)),
+            (Submissions::OPEN, "Return", Self::open_cursor),
+            (Submissions::FAV, "f", Self::fav_cursor),
+            (Submissions::MARK, "x", Self::toggle_mark),
+            (Submissions::REMOVE_MARKED, "<Shift>x", Self::remove_marked),
+            (Submissions::OPEN_IN_BROWSER, "o", Self::open_in_browser),
+            (Submissions::SEARCH, "<Primary>f", |t| t.search.toggle()),
+        ];
+
+        for &(detailed, accel, func) in actions.iter() {
+            let name = detailed.trim_start_matches("inbox.");
+            let action = gio::SimpleAction::new(name, None);
+
+            let weak = self.weak();
+            action.connect_activate(move |_, _| {
+                if let Some(this) = weak.upgrade() {
+                    func(&this);
+                }
+            });
+
+            group.add_action(&action);
+
+            let trigger = gtk::ShortcutTrigger::parse_string(accel);
+            let named = gtk::NamedAction::new(detailed);
+            let shortcut = gtk::Shortcut::new(trigger.as_ref(), Some(&named));
+            shortcuts.add_shortcut(&shortcut);
+        }
+
+        let artist_actions: [(&str, fn(&Self, String)); 3] = [
+            (Submissions::BLOCK_ARTIST, |t, a| t.block_artist(a, true)),
+            (Submissions::UNBLOCK_ARTIST, |t, a| t.block_artist(a, false)),
+            (Submissions::FILTER_ARTIST, Self::filter_artist),
+        ];
+
+        for &(detailed, func) in artist_actions.iter() {
+            let name = detailed.trim_start_matches("inbox.");
+            let action = gio::SimpleAction::new(
+                name,
+                Some(&String::static_variant_type()),
+            );
+
+            let weak = self.weak();
+            action.connect_activate(move |_, param| {
+                let artist = param.and_then(|p| p.get::<String>());
+                if let (Some(this), Some(artist)) = (weak.upgrade(), artist) {
+                    func(&this, artist);
+                }
+            });
+
+            group.add_action(&action);
+        }
+
+        self.scrolled_window
+            .insert_action_group("inbox", Some(&group));
+        self.scrolled_window.add_controller(&shortcuts);
+    }
+
+    fn move_cursor(&self, forward: bool) {
+        let next = match (self.page_list_view.cursor(), forward) {
+            (None, _) => 0,
+            (Some(p), true) => p + 1,
+            (Some(p), false) => p.saturating_sub(1),
+        };
+
+        self.page_list_view.set_cursor(next);
+    }
+
+    fn cursor(&self) -> Option<(u32, ViewKey)> {
+        let position = self.page_list_view.cursor()?;
+        let item = self.page_list_view.get(position)?;
+        Some((position, ViewKey::from(item.submission())))
+    }
+
+    fn open_cursor(&self) {
+        if let Some(position) = self.page_list_view.cursor() {
+            self.page_list_view.activate(position);
+        }
+    }
+
+    fn open_in_browser(&self) {
+        let key = match self.cursor() {
+            Some((_, k)) => k,
+            None => return,
+        };
+
+        let url = link::view_url(&key);
+        gtk::show_uri(self.window().as_ref(), url.as_str(), gdk::CURRENT_TIME);
+    }
+
+    fn window(&self) -> Option<gtk::Window> {
+        self.scrolled_window
+            .root()
+            .and_then(|r| r.downcast::<gtk::Window>().ok())
+    }
+
+    fn fav_cursor(&self) {
+        if let Some((_, key)) = self.cursor() {
+            self.fav(vec![key]);
+        }
+    }
+
+    fn fav(&self, keys: Vec<ViewKey>) {
+        let client = self.util.client().clone();
+        let task = self.util.spawn_local::<_, RequestError>(async move {
+            for key in keys {
+                // The fav link is only on the submission's own page.
+                let result = match client.view(key).await {
+                    Ok(view) => client.fav(view.fav_key().clone()).await,
+                    Err(e) => Err(e),
+                };
+
+                if let Err(e) = result {
+                    log::warn!("unable to fav submission: {}", e);
+                }
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    fn toggle_mark(&self) {
+        let (position, key) = match self.cursor() {
+            Some(c) => c,
+            None => return,
+        };
+
+        {
+            let mut marked = self.marked.borrow_mut();
+            match marked.iter().position(|k| *k == key) {
+                Some(index) => {
+                    marked.remove(index);
+                }
+                None => marked.push(key),
+            }
+        }
+
+        self.page_list_view.refresh(position);
+
+        // Move on, so a run of submissions can be marked in a row.
+        self.page_list_view.set_cursor(position + 1);
+    }
+
+    fn remove_marked(&self) {
+        let keys = self.marked.borrow().clone();
+        self.clear(keys);
+    }
+
+    fn filter_artist(&self, artist: String) {
+        self.search.set_artist(Some(artist));
+        self.apply_query();
+    }
+
+    fn block_artist(&self, artist: String, block: bool) {
+        let result = self.util.settings().update(|s| {
+            let artists = &mut s.blocklist.artists;
+            artists.retain(|a| !a.eq_ignore_ascii_case(&artist));
+
+            if block {
+                artists.push(artist);
+            }
+        });
+
+        if let Err(e) = result {
+            log::warn!("unable to store blocklist: {}", e);
+        }
+    }
+
+    /// Remove submissions from the inbox on the site, and from the list
+    /// once that works.
+    fn clear(&self, keys: Vec<ViewKey>) {
+        if keys.is_empty() {
+            return;
+        }
+
+        let weak = self.weak();
+        let client = self.util.client().clone();
+        let task = self.util.spawn_local::<_, RequestError>(async move {
+            let result = client.clear_submissions(keys.clone()).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            if let Err(e) = result {
+                log::warn!("unable to remove submissions: {}", e);
+                return Ok(());
+            }
+
+            this.marked.borrow_mut().retain(|k| !keys.contains(k));
+            this.page_list_view.remove_where(|item| {
+                keys.contains(&ViewKey::from(item.submission()))
+            });
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Call `func` with each submission activated in the list.
+    pub(crate) fn connect_open<F>(&self, func: F)
+    where
+        F: 'static + Fn(ViewKey),
+    {
+        self.page_list_view
+            .connect_activate(move |item: ListSubmission| {
+                func(ViewKey::from(item.submission()))
+            });
     }
 
+    /// Fetch the next page of submissions into the list, unless one is
+    /// already on its way or the last one has been.
     pub(crate) fn fetch(&self) {
-        eprintln!("fetching");
+        let key = match self.next.borrow().clone() {
+            Some(k) => k,
+            None => return,
+        };
+
         if self.fetching.replace(true) {
             return;
         }
 
+        self.error_row.hide();
+
         let this_weak = self.weak();
 
-        eprintln!("pre-spawn");
-        self.util.spawn_local::<_, RequestError>(async move {
-            eprintln!("post-spawn");
+        log::debug!("fetching submissions");
+        let task = self.util.spawn_local::<_, Infallible>(async move {
             let this = match this_weak.upgrade() {
                 Some(t) => t,
                 None => return Ok(()),
             };
 
-            let result = this
-                .util
-                .client()
-                .submissions(SubmissionsKey::oldest())
-                .await;
+            let result = this.util.client().submissions(key).await;
             this.fetching.replace(false);
 
-            let submissions: Vec<_> = result?
-                .into_items()
-                .into_iter()
-                .map(ListSubmission::new)
-                .collect();
+            let page = match result {
+                Ok(p) => p,
+                Err(e) => {
+                    let message = gettext("Unable to load submissions");
+                    this.error_label.set_text(&format!("{}: {}", message, e));
+                    this.error_row.show();
+                    return Ok(());
+                }
+            };
+            this.next.replace(page.next().cloned());
+
+            let submissions = page.into_items();
+
+            // Only what this page brought in is cleared, and only once it's
+            // in the list, so it can still be shown if clearing fails.
+            let blocked: Vec<_> = {
+                let blocklist = this.blocklist.borrow();
+                submissions
+                    .iter()
+                    .filter(|s| {
+                        blocklist.auto_clear && blocklist.blocks(s, None)
+                    })
+                    .map(ViewKey::from)
+                    .collect()
+            };
+
+            let submissions: Vec<_> =
+                submissions.into_iter().map(ListSubmission::new).collect();
 
-            eprintln!("got {} submissions", submissions.len());
-            this.page_list_view.add(submissions);
+            log::debug!("got {} submissions", submissions.len());
+
+            let weak = this.weak();
+            this.page_list_view.add_then(submissions, move || {
+                let this = match weak.upgrade() {
+                    Some(t) => t,
+                    None => return,
+                };
+
+                if !blocked.is_empty() {
+                    log::debug!(
+                        "clearing {} blocked submissions",
+                        blocked.len()
+                    );
+                    this.clear(blocked);
+                }
+            });
 
             Ok(())
         });
+        self.tasks.push(task);
     }
 }
@@ -0,0 +1,144 @@
+use crate::ptr::{Owned, Wrap};
+use crate::util::{Tasks, Util};
+
+use gettextrs::gettext;
+
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+
+use labrat::keys::UserKey;
+
+/// A user's profile, opened from a link.
+#[derive(Debug)]
+pub struct UserPage {
+    util: Util,
+    tasks: Tasks,
+
+    /// The user's name, once fetched.
+    loaded: RefCell<Option<String>>,
+
+    scrolled_window: gtk::ScrolledWindow,
+    avatar: adw::Avatar,
+    name: gtk::Label,
+    profile: gtk::Label,
+}
+
+impl UserPage {
+    /// Create the page and start fetching it, calling `on_loaded` once
+    /// there's a name to show.
+    pub(crate) fn new<F>(util: Util, key: UserKey, on_loaded: F) -> Owned<Self>
+    where
+        F: 'static + Fn(),
+    {
+        let avatar = adw::Avatar::new(96, None, true);
+
+        let name = gtk::LabelBuilder::new()
+            .label(&gettext("Loading…"))
+            .wrap(true)
+            .build();
+        name.add_css_class("title-1");
+
+        let profile = gtk::LabelBuilder::new()
+            .wrap(true)
+            .selectable(true)
+            .xalign(0.)
+            .yalign(0.)
+            .build();
+
+        let box_ = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .margin_top(18)
+            .margin_bottom(18)
+            .margin_start(18)
+            .margin_end(18)
+            .build();
+
+        box_.append(&avatar);
+        box_.append(&name);
+        box_.append(&profile);
+
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_child(Some(&box_));
+
+        let owned = Owned::new(Self {
+            util,
+            tasks: Tasks::default(),
+            loaded: Default::default(),
+            scrolled_window,
+            avatar,
+            name,
+            profile,
+        });
+
+        owned.fetch(key, on_loaded);
+
+        owned
+    }
+}
+
+impl Wrap<UserPage> {
+    pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.scrolled_window
+    }
+
+    /// The user's name, once it has loaded.
+    pub(crate) fn name(&self) -> Option<String> {
+        self.loaded.borrow().clone()
+    }
+
+    fn set_avatar(&self, pixbuf: gdk_pixbuf::Pixbuf) {
+        let image = move |_: i32| Some(pixbuf.clone());
+        self.avatar.set_image_load_func(Some(Box::new(image)));
+    }
+
+    fn fetch<F>(&self, key: UserKey, on_loaded: F)
+    where
+        F: 'static + Fn(),
+    {
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let client = match weak.upgrade() {
+                Some(t) => t.util.client().clone(),
+                None => return Ok(()),
+            };
+
+            let result = client.user(key).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            let user = match result {
+                Ok(u) => u,
+                Err(e) => {
+                    this.name.set_text(&format!(
+                        "{}: {}",
+                        gettext("Unable to load user"),
+                        e
+                    ));
+                    return Ok(());
+                }
+            };
+
+            this.name.set_text(user.name());
+            this.avatar.set_text(Some(user.name()));
+            this.profile.set_text(user.profile());
+            this.loaded.replace(Some(user.name().to_owned()));
+            on_loaded();
+
+            let uri = user.avatar().to_string();
+            match this.util.fetch_pixbuf(&uri).await {
+                Ok(pixbuf) => this.set_avatar(pixbuf),
+                Err(e) => log::warn!("unable to load {}: {}", uri, e),
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+}
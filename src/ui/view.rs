@@ -0,0 +1,198 @@
+use crate::bridge::errors::RequestError;
+use crate::ptr::{Owned, Wrap};
+use crate::settings::{Content, Visibility};
+use crate::util::{Tasks, Util};
+use crate::widgets::FillImage;
+
+use gettextrs::gettext;
+
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+
+use labrat::keys::ViewKey;
+use labrat::resources::Rating;
+
+/// A single submission, opened from a link.
+#[derive(Debug)]
+pub struct ViewPage {
+    util: Util,
+    tasks: Tasks,
+
+    /// Title and artist, once fetched.
+    loaded: RefCell<Option<(String, String)>>,
+    rating: RefCell<Option<Rating>>,
+
+    box_: gtk::Box,
+    title: gtk::Label,
+    artist: gtk::Label,
+    image: FillImage,
+    spinner: gtk::Spinner,
+}
+
+impl ViewPage {
+    /// Create the page and start fetching it, calling `on_loaded` once
+    /// there's a title to show.
+    pub(crate) fn new<F>(util: Util, key: ViewKey, on_loaded: F) -> Owned<Self>
+    where
+        F: 'static + Fn(),
+    {
+        let title = gtk::LabelBuilder::new().wrap(true).xalign(0.).build();
+        title.add_css_class("title-1");
+
+        let artist = gtk::LabelBuilder::new().xalign(0.).build();
+        artist.add_css_class("dim-label");
+
+        let image = FillImage::new();
+        image.set_hexpand(true);
+        image.set_vexpand(true);
+
+        let spinner = gtk::SpinnerBuilder::new()
+            .spinning(true)
+            .halign(gtk::Align::Center)
+            .valign(gtk::Align::Center)
+            .vexpand(true)
+            .build();
+
+        let box_ = gtk::BoxBuilder::new()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .margin_top(12)
+            .margin_bottom(12)
+            .margin_start(12)
+            .margin_end(12)
+            .build();
+
+        box_.append(&spinner);
+        box_.append(&image);
+        box_.append(&title);
+        box_.append(&artist);
+
+        let owned = Owned::new(Self {
+            util,
+            tasks: Tasks::default(),
+            loaded: Default::default(),
+            rating: Default::default(),
+            box_,
+            title,
+            artist,
+            image,
+            spinner,
+        });
+
+        owned.fetch(key, on_loaded);
+        owned.watch_settings();
+
+        owned
+    }
+}
+
+impl Wrap<ViewPage> {
+    pub(crate) fn widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.box_
+    }
+
+    /// The submission's title, once it has loaded.
+    pub(crate) fn title(&self) -> Option<String> {
+        self.loaded.borrow().as_ref().map(|(t, _)| t.clone())
+    }
+
+    pub(crate) fn artist(&self) -> Option<String> {
+        self.loaded.borrow().as_ref().map(|(_, a)| a.clone())
+    }
+
+    fn fetch<F>(&self, key: ViewKey, on_loaded: F)
+    where
+        F: 'static + Fn(),
+    {
+        let weak = self.weak();
+        let task = self.util.spawn_local::<_, RequestError>(async move {
+            let client = match weak.upgrade() {
+                Some(t) => t.util.client().clone(),
+                None => return Ok(()),
+            };
+
+            let result = client.view(key).await;
+
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+
+            this.spinner.hide();
+
+            let view = match result {
+                Ok(v) => v,
+                Err(e) => {
+                    this.title.set_text(&format!(
+                        "{}: {}",
+                        gettext("Unable to load submission"),
+                        e
+                    ));
+                    return Ok(());
+                }
+            };
+
+            this.title.set_text(view.title());
+            this.loaded.replace(Some((
+                view.title().to_owned(),
+                view.artist().name().to_owned(),
+            )));
+            this.artist.set_text(view.artist().name());
+            this.rating.replace(Some(view.rating()));
+            this.apply_visibility(&this.util.settings().get().content);
+            on_loaded();
+
+            let uri = view.fullview().to_string();
+            match this.util.fetch_pixbuf(&uri).await {
+                Ok(pixbuf) => this.image.set_pixbuf(pixbuf),
+                Err(e) => log::warn!("unable to load {}: {}", uri, e),
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+
+    /// Blur the image if its rating calls for it.
+    ///
+    /// Submissions that would be hidden from the inbox are blurred here
+    /// instead, since this one was asked for.
+    fn apply_visibility(&self, content: &Content) {
+        let rating = match self.rating.borrow().clone() {
+            Some(r) => r,
+            None => return,
+        };
+
+        let blurred = content.visibility(rating) != Visibility::Show;
+        self.image.set_blurred(blurred);
+    }
+
+    fn watch_settings(&self) {
+        let mut settings = self.util.settings().subscribe();
+        let weak = self.weak();
+
+        let task = self.util.spawn_local::<_, Infallible>(async move {
+            let mut current = settings.borrow().content.clone();
+
+            while settings.changed().await.is_ok() {
+                let this = match weak.upgrade() {
+                    Some(t) => t,
+                    None => break,
+                };
+
+                let new = settings.borrow().content.clone();
+                if new != current {
+                    this.apply_visibility(&new);
+                    current = new;
+                }
+            }
+
+            Ok(())
+        });
+
+        self.tasks.push(task);
+    }
+}
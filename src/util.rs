@@ -1,13 +1,24 @@
+use crate::bridge::backend;
 use crate::bridge::Client;
+use crate::settings;
 
 use directories::ProjectDirs;
 
+use futures_channel::oneshot;
+
 pub use self::error::Error;
 
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
 
 use soup::{CacheExt, RequestExt, SessionExt};
 
+use reqwest::Url;
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 mod error {
@@ -27,6 +38,60 @@ mod error {
 
         /// Converting path to string failed.
         PathInvalidUtf8 { backtrace: Backtrace },
+
+        /// Unable to remember which account is active.
+        ActiveAccount {
+            backtrace: Backtrace,
+            source: std::io::Error,
+        },
+
+        /// Unable to remove files belonging to an account.
+        RemoveAccountFiles {
+            backtrace: Backtrace,
+            source: std::io::Error,
+        },
+    }
+}
+
+/// Handle to a future spawned with [`Util::spawn_local`].
+///
+/// Dropping the handle detaches the future; use [`Task::cancel`] to stop it.
+#[derive(Debug)]
+pub struct Task(oneshot::Sender<()>);
+
+impl Task {
+    /// Drop the spawned future without polling it again, along with any
+    /// bridge request it is waiting on.
+    pub fn cancel(self) {
+        self.0.send(()).ok();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.0.is_canceled()
+    }
+}
+
+/// Tasks owned by a page, cancelled together when the page goes away.
+#[derive(Debug, Default)]
+pub struct Tasks(RefCell<Vec<Task>>);
+
+impl Tasks {
+    pub fn push(&self, task: Task) {
+        let mut tasks = self.0.borrow_mut();
+        tasks.retain(|t| !t.is_finished());
+        tasks.push(task);
+    }
+
+    pub fn cancel_all(&self) {
+        for task in self.0.borrow_mut().drain(..) {
+            task.cancel();
+        }
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        self.cancel_all();
     }
 }
 
@@ -35,14 +100,72 @@ struct Inner {
     dirs: ProjectDirs,
     threads: glib::ThreadPool,
     http: soup::Session,
-    http_cache: soup::Cache,
+    http_cache: RefCell<soup::Cache>,
+
+    /// Base of every account's cache, fixed for as long as the app runs.
+    cache_dir: PathBuf,
+    base: Option<Url>,
+    account: RefCell<Option<String>>,
     client: Client,
+    settings: settings::Store,
+}
+
+impl Inner {
+    fn active_account_path(dirs: &ProjectDirs) -> PathBuf {
+        dirs.config_dir().join("active-account")
+    }
+
+    /// Directory under `base` holding files belonging to `account`.
+    fn account_dir(base: &Path, account: Option<&str>) -> PathBuf {
+        let account = match account {
+            Some(a) => a,
+            None => return base.to_owned(),
+        };
+
+        // Percent-encode everything else, so different names never share a
+        // directory.
+        let mut escaped = String::with_capacity(account.len());
+        for byte in account.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => {
+                    escaped.push(char::from(byte))
+                }
+                _ => escaped.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+
+        base.join("accounts").join(escaped)
+    }
+
+    /// Where cached images go, unless the settings say otherwise.
+    fn cache_dir(dirs: &ProjectDirs, cache: &settings::Cache) -> PathBuf {
+        match cache.location {
+            Some(ref l) => l.clone(),
+            None => dirs.cache_dir().to_owned(),
+        }
+    }
+
+    fn http_cache(
+        cache_dir: &Path,
+        account: Option<&str>,
+        max_size: u32,
+    ) -> Result<soup::Cache, Error> {
+        let dir = Self::account_dir(cache_dir, account).join("soup");
+        let http_cache = soup::Cache::new(
+            Some(dir.to_str().context(error::PathInvalidUtf8)?),
+            soup::CacheType::SingleUser,
+        );
+        http_cache.set_max_size(max_size);
+        http_cache.load();
+        Ok(http_cache)
+    }
 }
 
 impl Drop for Inner {
     fn drop(&mut self) {
-        self.http_cache.dump();
-        self.http_cache.flush();
+        let http_cache = self.http_cache.borrow();
+        http_cache.dump();
+        http_cache.flush();
     }
 }
 
@@ -51,7 +174,10 @@ impl Drop for Inner {
 pub struct Util(Rc<Inner>);
 
 impl Util {
-    pub fn new(client: Client) -> Result<Self, Error> {
+    pub fn new(
+        client: Client,
+        settings: settings::Store,
+    ) -> Result<Self, Error> {
         let dirs = ProjectDirs::from(
             crate::QUALIFIER,
             crate::ORGANIZATION,
@@ -62,30 +188,148 @@ impl Util {
         let threads =
             glib::ThreadPool::new_shared(None).context(error::Threads)?;
 
-        // TODO: Set a max size for the HTTP cache?
-        let http_cache_dir = dirs.cache_dir().join("soup");
-        let http_cache = soup::Cache::new(
-            Some(http_cache_dir.to_str().context(error::PathInvalidUtf8)?),
-            soup::CacheType::SingleUser,
-        );
-        http_cache.load();
+        let account =
+            match std::fs::read_to_string(Inner::active_account_path(&dirs)) {
+                Ok(a) if !a.trim().is_empty() => Some(a.trim().to_owned()),
+                Ok(_) => None,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e).context(error::ActiveAccount),
+            };
+
+        let cache = settings.get().cache;
+        let cache_dir = Inner::cache_dir(&dirs, &cache);
+        let http_cache = Inner::http_cache(
+            &cache_dir,
+            account.as_deref(),
+            cache.max_bytes(),
+        )?;
 
         let http = soup::Session::new();
         http.add_feature(&http_cache);
 
-        Ok(Util(Rc::new(Inner {
+        let util = Util(Rc::new(Inner {
             dirs,
             http,
-            http_cache,
+            http_cache: RefCell::new(http_cache),
+            cache_dir,
+            base: backend::base_url(),
+            account: RefCell::new(account),
             threads,
             client,
-        })))
+            settings,
+        }));
+
+        util.watch_cache_size();
+
+        Ok(util)
     }
 
     pub fn client(&self) -> &Client {
         &self.0.client
     }
 
+    pub fn settings(&self) -> &settings::Store {
+        &self.0.settings
+    }
+
+    /// Keep the HTTP cache within the size in the settings as they change.
+    fn watch_cache_size(&self) {
+        let mut settings = self.settings().subscribe();
+        let weak = Rc::downgrade(&self.0);
+
+        // Ends once the settings store is dropped along with everything else.
+        self.spawn_local::<_, Infallible>(async move {
+            while settings.changed().await.is_ok() {
+                let inner = match weak.upgrade() {
+                    Some(i) => i,
+                    None => break,
+                };
+
+                let max_size = settings.borrow().cache.max_bytes();
+                inner.http_cache.borrow().set_max_size(max_size);
+            }
+
+            Ok(())
+        });
+    }
+
+    /// Name of the account the app was last switched to.
+    pub fn account(&self) -> Option<String> {
+        self.0.account.borrow().clone()
+    }
+
+    /// Switch caches and per-account files over to `account`, and remember
+    /// it for the next launch.
+    ///
+    /// This doesn't log the bridge in; see `Client::login`.
+    pub fn set_account(&self, account: Option<&str>) -> Result<(), Error> {
+        if self.0.account.borrow().as_deref() == account {
+            return Ok(());
+        }
+
+        let max_size = self.settings().get().cache.max_bytes();
+        let http_cache =
+            Inner::http_cache(&self.0.cache_dir, account, max_size)?;
+
+        // Nothing is switched over unless the account will be remembered,
+        // so the next launch can't start with another account's cache.
+        let path = Inner::active_account_path(&self.0.dirs);
+        std::fs::create_dir_all(self.0.dirs.config_dir())
+            .and_then(|_| std::fs::write(path, account.unwrap_or_default()))
+            .context(error::ActiveAccount)?;
+
+        let old = self.0.http_cache.replace(http_cache);
+        old.dump();
+        old.flush();
+
+        self.0.http.remove_feature(&old);
+        self.0.http.add_feature(&*self.0.http_cache.borrow());
+
+        self.0.account.replace(account.map(str::to_owned));
+
+        Ok(())
+    }
+
+    /// Throw away everything cached or stored for the active account, and
+    /// switch back to no account.
+    pub fn forget_account(&self) -> Result<(), Error> {
+        self.0.http_cache.borrow().clear();
+
+        let account = match self.account() {
+            Some(a) => a,
+            None => return Ok(()),
+        };
+
+        self.set_account(None)?;
+
+        let dirs = &self.0.dirs;
+        let bases = [&*self.0.cache_dir, dirs.data_dir(), dirs.config_dir()];
+
+        for base in bases.iter() {
+            let dir = Inner::account_dir(base, Some(&account));
+
+            match std::fs::remove_dir_all(dir) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => return Err(e).context(error::RemoveAccountFiles),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        self.0.dirs.config_dir()
+    }
+
+    /// Directory for files that belong to the active account.
+    pub fn account_data_dir(&self) -> PathBuf {
+        Inner::account_dir(
+            self.0.dirs.data_dir(),
+            self.0.account.borrow().as_deref(),
+        )
+    }
+
     pub async fn spawn_background<F, T>(&self, func: F) -> T
     where
         T: 'static + Send,
@@ -95,30 +339,37 @@ impl Util {
         self.0.threads.push_future(func).unwrap().await
     }
 
-    pub fn spawn_local<F, E>(&self, fut: F)
+    pub fn spawn_local<F, E>(&self, fut: F) -> Task
     where
         F: 'static + std::future::Future<Output = Result<(), E>>,
         E: std::error::Error,
     {
-        eprintln!("Spawn local?");
+        let (cancel, cancelled) = oneshot::channel();
+
         glib::MainContext::default().spawn_local(async move {
-            eprintln!("local spawned");
-            if let Err(e) = fut.await {
-                eprintln!("{}", e);
-                std::process::abort();
-                // TODO: Show an error instead of exiting I guess.
+            let result = tokio::select! {
+                result = fut => result,
+                Ok(()) = cancelled => return,
+            };
+
+            // Anything the user should see is shown by the task itself.
+            if let Err(e) = result {
+                log::error!("{}", e);
             }
         });
+
+        Task(cancel)
     }
 
     pub async fn http_get(
         &self,
         uri: &str,
     ) -> Result<gio::InputStream, glib::Error> {
+        let uri = resolve(self.0.base.as_ref(), uri);
         let stream = self
             .0
             .http
-            .request_http("GET", uri)?
+            .request_http("GET", &uri)?
             .send_async_future()
             .await?;
         Ok(stream)
@@ -134,3 +385,78 @@ impl Util {
         Ok(pixbuf)
     }
 }
+
+/// Where to actually fetch `uri` from.
+///
+/// With a base URL set, every host is served by the stand-in server, so
+/// `https://host/path` becomes `<base>/host/path`.
+fn resolve<'a>(base: Option<&Url>, uri: &'a str) -> Cow<'a, str> {
+    let base = match base {
+        Some(b) => b,
+        None => return Cow::Borrowed(uri),
+    };
+
+    let resolved = Url::parse(uri).ok().and_then(|u| {
+        let path = format!("{}{}", u.host_str()?, u.path());
+        base.join(&path).ok()
+    });
+
+    match resolved {
+        Some(r) => Cow::Owned(r.into()),
+        None => Cow::Borrowed(uri),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_dir_keeps_plain_names() {
+        let base = Path::new("/base");
+        let dir = Inner::account_dir(base, Some("some-user_1"));
+        assert_eq!(dir, Path::new("/base/accounts/some-user_1"));
+    }
+
+    #[test]
+    fn account_dir_escapes_reversibly() {
+        let base = Path::new("/base");
+
+        let dotted = Inner::account_dir(base, Some("a.b"));
+        let underscored = Inner::account_dir(base, Some("a_b"));
+        let escaped = Inner::account_dir(base, Some("a%2Eb"));
+
+        assert_eq!(dotted, Path::new("/base/accounts/a%2Eb"));
+        assert_ne!(dotted, underscored);
+        assert_ne!(dotted, escaped);
+    }
+
+    #[test]
+    fn account_dir_without_account() {
+        let base = Path::new("/base");
+        assert_eq!(Inner::account_dir(base, None), base);
+    }
+
+    #[test]
+    fn resolve_without_base() {
+        let uri = "https://d.furaffinity.net/art/a/1.png";
+        assert_eq!(resolve(None, uri), uri);
+    }
+
+    #[test]
+    fn resolve_moves_hosts_under_base() {
+        let base = Url::parse("http://127.0.0.1:8080/").unwrap();
+        let uri = "https://d.furaffinity.net/art/a/1.png?x=1";
+
+        assert_eq!(
+            resolve(Some(&base), uri),
+            "http://127.0.0.1:8080/d.furaffinity.net/art/a/1.png"
+        );
+    }
+
+    #[test]
+    fn resolve_keeps_unparsable() {
+        let base = Url::parse("http://127.0.0.1:8080/").unwrap();
+        assert_eq!(resolve(Some(&base), "not a url"), "not a url");
+    }
+}
@@ -1,22 +1,53 @@
 use gdk_pixbuf::prelude::*;
 
+use gettextrs::gettext;
+
+use gtk::graphene;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::convert::TryFrom;
 
 mod imp {
     use super::*;
 
-    #[derive(Debug, Default, Clone)]
+    const BLUR_RADIUS: f64 = 32.;
+
+    #[derive(Debug, Clone)]
     pub struct FillImage {
         area: gtk::DrawingArea,
-        //btn: gtk::Button,
+        btn: gtk::Button,
         pixbuf: RefCell<Option<gdk_pixbuf::Pixbuf>>,
+        blurred: Cell<bool>,
+    }
+
+    impl Default for FillImage {
+        fn default() -> Self {
+            let btn = gtk::ButtonBuilder::new()
+                .label(&gettext("Show"))
+                .halign(gtk::Align::Center)
+                .valign(gtk::Align::Center)
+                .visible(false)
+                .build();
+            btn.add_css_class("osd");
+
+            Self {
+                area: Default::default(),
+                btn,
+                pixbuf: Default::default(),
+                blurred: Cell::new(false),
+            }
+        }
     }
 
     impl FillImage {
+        pub fn set_blurred(&self, blurred: bool) {
+            self.blurred.set(blurred);
+            self.btn.set_visible(blurred);
+            self.area.queue_draw();
+        }
+
         pub fn set_pixbuf(&self, pixbuf: gdk_pixbuf::Pixbuf) {
             let mut pb = self.pixbuf.borrow_mut();
             *pb = Some(pixbuf);
@@ -94,16 +125,25 @@ mod imp {
     impl ObjectImpl for FillImage {
         fn constructed(&self, obj: &Self::Type) {
             self.parent_constructed(obj);
-            //self.btn.set_parent(obj);
             self.area.set_parent(obj);
             self.area.set_hexpand(true);
             self.area.set_vexpand(true);
             self.area.set_draw_func(Self::draw);
+
+            // On top of the image, so it's drawn over it and gets clicks.
+            self.btn.set_parent(obj);
+
+            let weak = obj.downgrade();
+            self.btn.connect_clicked(move |_| {
+                if let Some(obj) = weak.upgrade() {
+                    Self::from_instance(&obj).set_blurred(false);
+                }
+            });
         }
 
         fn dispose(&self, _: &Self::Type) {
             self.area.unparent();
-            //self.btn.unparent();
+            self.btn.unparent();
         }
     }
 
@@ -120,7 +160,32 @@ mod imp {
             baseline: i32,
         ) {
             self.area.allocate(width, height, baseline, None);
-            // self.btn.allocate(width, height, baseline, None);
+
+            // Centered by its alignment.
+            self.btn.allocate(width, height, baseline, None);
+        }
+
+        fn snapshot(&self, obj: &Self::Type, snapshot: &gtk::Snapshot) {
+            if !self.blurred.get() {
+                obj.snapshot_child(&self.area, snapshot);
+                return;
+            }
+
+            // Keep the blur from bleeding into neighbouring widgets.
+            let bounds = graphene::Rect::new(
+                0.,
+                0.,
+                obj.width() as f32,
+                obj.height() as f32,
+            );
+
+            snapshot.push_clip(&bounds);
+            snapshot.push_blur(BLUR_RADIUS);
+            obj.snapshot_child(&self.area, snapshot);
+            snapshot.pop();
+            snapshot.pop();
+
+            obj.snapshot_child(&self.btn, snapshot);
         }
 
         fn measure(
@@ -134,8 +199,10 @@ mod imp {
             nat_base: &mut i32,
         ) {
             let child = self.area.measure(orientation, for_size);
-            *min = child.0;
-            *nat = child.1;
+            let (btn_min, btn_nat, _, _) =
+                self.btn.measure(orientation, for_size);
+            *min = child.0.max(btn_min);
+            *nat = child.1.max(btn_nat);
             *min_base = child.2;
             *nat_base = child.3;
         }
@@ -160,4 +227,10 @@ impl FillImage {
         let instance = imp::FillImage::from_instance(self);
         instance.set_pixbuf(pixbuf);
     }
+
+    /// Blur the image, with a button on top to show it.
+    pub fn set_blurred(&self, blurred: bool) {
+        let instance = imp::FillImage::from_instance(self);
+        instance.set_blurred(blurred);
+    }
 }
@@ -3,11 +3,12 @@ use crate::ptr::{Owned, Ref, Wrap};
 use gio::prelude::*;
 
 use glib::signal::SignalHandlerId;
+use glib::ToVariant;
 
 use gtk::prelude::*;
 
 use std::borrow::Borrow;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::marker::PhantomData;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -15,10 +16,14 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 #[derive(Debug)]
 pub struct PageListView<T> {
     _p: PhantomData<T>,
-    end_diff: f64,
+    end_diff: Cell<f64>,
     list_store: gio::ListStore,
+    filter_model: gtk::FilterListModel,
+    sort_model: gtk::SortListModel,
     multi_selection: gtk::MultiSelection,
-    list_view: gtk::ListView,
+
+    /// A grid, so the same view can show one item per row or several.
+    list_view: gtk::GridView,
     scroll_signal: RefCell<Option<(gtk::Adjustment, SignalHandlerId)>>,
 
     adding: AtomicUsize,
@@ -37,20 +42,30 @@ where
 
         let list_store = gio::ListStore::new(T::static_type());
 
-        let multi_selection = gtk::MultiSelection::new(Some(&list_store));
+        let filter_model =
+            gtk::FilterListModel::new(Some(&list_store), gtk::NONE_FILTER);
+
+        let sort_model =
+            gtk::SortListModel::new(Some(&filter_model), gtk::NONE_SORTER);
+
+        let multi_selection = gtk::MultiSelection::new(Some(&sort_model));
 
-        let list_view = gtk::ListViewBuilder::new()
+        let list_view = gtk::GridViewBuilder::new()
             .factory(factory)
             .model(&multi_selection)
+            .single_click_activate(true)
+            .max_columns(1)
             .build();
 
         let owned = Owned::new(Self {
             _p: PhantomData,
             scroll_signal: Default::default(),
             multi_selection,
+            filter_model,
+            sort_model,
             list_store,
             list_view,
-            end_diff: 200.,
+            end_diff: Cell::new(200.),
             adding: AtomicUsize::new(0),
         });
 
@@ -77,10 +92,92 @@ where
         &self.list_view
     }
 
+    /// Number of items loaded, including any filtered out.
     pub fn len(&self) -> u32 {
         self.list_store.n_items()
     }
 
+    /// How close to the end, in pixels, scrolling has to get before more
+    /// items are fetched.
+    pub fn set_end_diff(&self, end_diff: f64) {
+        self.end_diff.set(end_diff);
+    }
+
+    /// Show items in a grid of up to `columns` per row, or in a list for
+    /// one.
+    pub fn set_columns(&self, columns: u32) {
+        self.list_view.set_min_columns(columns.min(2));
+        self.list_view.set_max_columns(columns.max(1));
+    }
+
+    /// Only show items `filter` matches.
+    pub fn set_filter<F>(&self, filter: Option<&F>)
+    where
+        F: IsA<gtk::Filter>,
+    {
+        self.filter_model.set_filter(filter);
+    }
+
+    /// Show items in the order `sorter` puts them, rather than the order
+    /// they were added.
+    pub fn set_sorter<S>(&self, sorter: Option<&S>)
+    where
+        S: IsA<gtk::Sorter>,
+    {
+        self.sort_model.set_sorter(sorter);
+    }
+
+    /// Position of the first selected item, which keyboard navigation
+    /// treats as the cursor.
+    pub fn cursor(&self) -> Option<u32> {
+        let selection = self.multi_selection.selection();
+        if selection.is_empty() {
+            None
+        } else {
+            Some(selection.minimum())
+        }
+    }
+
+    /// Select only the item at `position` and scroll it into view.
+    pub fn set_cursor(&self, position: u32) {
+        if position >= self.multi_selection.n_items() {
+            return;
+        }
+
+        self.multi_selection.select_item(position, true);
+        self.list_view.activate_action(
+            "list.scroll-to-item",
+            Some(&position.to_variant()),
+        );
+    }
+
+    /// Activate the item at `position`, as if it had been clicked.
+    pub fn activate(&self, position: u32) {
+        self.list_view.activate_action(
+            "list.activate-item",
+            Some(&position.to_variant()),
+        );
+    }
+
+    /// Bind every item again, to pick up changes to how they're shown.
+    pub fn refresh_all(&self) {
+        let len = self.len();
+        self.list_store.items_changed(0, len, len);
+    }
+
+    /// Call `func` whenever items are added or removed.
+    pub fn connect_items_changed<F>(&self, func: F)
+    where
+        F: 'static + Fn(),
+    {
+        self.list_store
+            .connect_items_changed(move |_, _, removed, added| {
+                if removed != added {
+                    func();
+                }
+            });
+    }
+
     fn on_notify_vadjustment<F>(&self, fetch: &Rc<F>)
     where
         F: 'static + Fn(Ref<PageListView<T>>),
@@ -101,7 +198,7 @@ where
                     }
 
                     let max = vadj.upper() - vadj.page_size();
-                    if vadj.value() >= max - this.end_diff {
+                    if vadj.value() >= max - this.end_diff.get() {
                         fetch_clone(this);
                     }
                 }
@@ -126,15 +223,104 @@ where
         Some(obj.downcast().unwrap())
     }
 
+    /// The item shown at `position`.
+    pub fn get(&self, position: u32) -> Option<T> {
+        let obj = self.multi_selection.get_object(position)?;
+        Some(obj.downcast().unwrap())
+    }
+
+    /// Bind the item shown at `position` again, to pick up changes to how
+    /// it's shown.
+    pub fn refresh(&self, position: u32) {
+        let item = match self.multi_selection.get_object(position) {
+            Some(i) => i,
+            None => return,
+        };
+
+        let stored = (0..self.len())
+            .find(|&i| self.list_store.get_object(i).as_ref() == Some(&item));
+
+        if let Some(index) = stored {
+            self.list_store.items_changed(index, 1, 1);
+        }
+    }
+
+    /// Bind every item `func` returns `true` for again, to pick up changes
+    /// to how they're shown.
+    pub fn refresh_where<F>(&self, func: F)
+    where
+        F: Fn(&T) -> bool,
+    {
+        for index in 0..self.len() {
+            let item = self.list_store.get_object(index).unwrap();
+            if func(item.downcast_ref().unwrap()) {
+                self.list_store.items_changed(index, 1, 1);
+            }
+        }
+    }
+
+    /// Every item `func` returns `true` for, shown or not.
+    pub fn collect_where<F>(&self, func: F) -> Vec<T>
+    where
+        F: Fn(&T) -> bool,
+    {
+        (0..self.len())
+            .filter_map(|index| self.list_store.get_object(index))
+            .filter_map(|item| item.downcast::<T>().ok())
+            .filter(|item| func(item))
+            .collect()
+    }
+
+    /// Remove every item `func` returns `true` for, shown or not.
+    pub fn remove_where<F>(&self, func: F)
+    where
+        F: Fn(&T) -> bool,
+    {
+        for index in (0..self.len()).rev() {
+            let item = self.list_store.get_object(index).unwrap();
+            if func(item.downcast_ref().unwrap()) {
+                self.list_store.remove(index);
+            }
+        }
+    }
+
+    pub fn connect_activate<F>(&self, func: F)
+    where
+        F: 'static + Fn(T),
+    {
+        let weak = self.weak();
+        self.list_view.connect_activate(move |_, position| {
+            let this = match weak.upgrade() {
+                Some(t) => t,
+                None => return,
+            };
+
+            if let Some(item) = this.get(position) {
+                func(item);
+            }
+        });
+    }
+
     pub fn add<I>(&self, items: I)
     where
         I: 'static + IntoIterator,
         <I as IntoIterator>::Item: Borrow<T>,
+    {
+        self.add_then(items, || ());
+    }
+
+    /// Add `items`, then call `func` once they're all in the list.
+    pub fn add_then<I, F>(&self, items: I, func: F)
+    where
+        I: 'static + IntoIterator,
+        <I as IntoIterator>::Item: Borrow<T>,
+        F: 'static + FnOnce(),
     {
         self.adding.fetch_add(1, Ordering::SeqCst);
 
         let weak = self.weak();
         let mut iter = items.into_iter();
+        let mut func = Some(func);
 
         glib::source::idle_add_local(move || {
             let this = match weak.upgrade() {
@@ -148,6 +334,11 @@ where
             } else {
                 let val = this.adding.fetch_sub(1, Ordering::SeqCst);
                 assert_ne!(val, 0);
+
+                if let Some(func) = func.take() {
+                    func();
+                }
+
                 glib::Continue(false)
             }
         });
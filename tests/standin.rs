@@ -0,0 +1,197 @@
+//! Drives the bridge against a stand-in server, checking what reaches the
+//! site for each kind of request.
+
+#[path = "support/standin.rs"]
+mod standin;
+
+use labrat::keys::{CommentReplyKey, FavKey, SubmissionsKey, ViewKey};
+
+use labrat_gtk::bridge::backend::Site;
+use labrat_gtk::bridge::{Bridge, Config};
+
+use reqwest::Url;
+
+use self::standin::{Received, StandIn};
+
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+const COOKIES: &str = "a=first; b=second";
+
+/// Pages and images recorded for the tests.
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn site_url(path: &str) -> Url {
+    Url::parse("https://www.furaffinity.net/")
+        .unwrap()
+        .join(path)
+        .unwrap()
+}
+
+async fn start() -> (StandIn, Bridge) {
+    let standin = StandIn::bind(fixtures(), "127.0.0.1:0").await.unwrap();
+    let site = Site::new(Some(standin.url()), None).unwrap();
+
+    let config = Config {
+        requests_per_second: 0.,
+        ..Config::default()
+    };
+
+    let bridge = Bridge::with_backend(config, Box::new(site));
+    bridge.client().login(COOKIES).await.unwrap();
+
+    (standin, bridge)
+}
+
+/// The only request made since the last check.
+fn single(standin: &StandIn) -> Received {
+    let mut received = standin.take_received();
+    assert_eq!(received.len(), 1, "{:?}", received);
+    received.remove(0)
+}
+
+fn assert_logged_in(received: &Received) {
+    let cookie = received.cookie.as_deref().unwrap_or_default();
+
+    for pair in COOKIES.split("; ") {
+        assert!(cookie.contains(pair), "{:?} lacks {}", cookie, pair);
+    }
+}
+
+#[tokio::test]
+async fn paging_sends_cookies() {
+    let (standin, bridge) = start().await;
+    let client = bridge.client();
+
+    // No recorded submissions, so the requests themselves are what's
+    // checked.
+    client.submissions(SubmissionsKey::oldest()).await.ok();
+    let oldest = single(&standin);
+
+    client.submissions(SubmissionsKey::newest()).await.ok();
+    let newest = single(&standin);
+
+    for page in &[&oldest, &newest] {
+        assert_eq!(page.method, "GET");
+        assert!(page.target.starts_with("/msg/submissions/"), "{:?}", page);
+        assert_logged_in(page);
+    }
+
+    assert_ne!(oldest.target, newest.target);
+
+    bridge.join();
+}
+
+#[tokio::test]
+async fn logout_drops_cookies() {
+    let (standin, bridge) = start().await;
+    let client = bridge.client();
+
+    client.logout().await.unwrap();
+    client.submissions(SubmissionsKey::oldest()).await.ok();
+
+    let received = single(&standin);
+    assert_eq!(received.cookie, None);
+
+    bridge.join();
+}
+
+#[tokio::test]
+async fn missing_pages_are_not_retried() {
+    let (_standin, bridge) = start().await;
+
+    let key = ViewKey::try_from(site_url("/view/1234/")).unwrap();
+    let error = match bridge.client().view(key).await {
+        Ok(_) => panic!("there's no page to parse"),
+        Err(e) => e,
+    };
+
+    // Not found is fatal, so the error comes from the first attempt.
+    assert!(error.to_string().contains("1 attempt"), "{}", error);
+
+    bridge.join();
+}
+
+#[tokio::test]
+async fn view_is_parsed_with_its_image() {
+    let (standin, bridge) = start().await;
+
+    let key = ViewKey::try_from(site_url("/view/4321/")).unwrap();
+    let view = bridge.client().view(key).await.unwrap();
+
+    assert_eq!(view.title(), "Fixture Title");
+    assert_eq!(view.artist().name(), "fixture");
+    assert_logged_in(&single(&standin));
+
+    // Images are fetched from `<base>/<host>/<path>`, like `Util::http_get`.
+    let fullview = view.fullview();
+    let path = format!("{}{}", fullview.host_str().unwrap(), fullview.path());
+    let response = reqwest::get(standin.url().join(&path).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.headers()["content-type"], "image/png");
+
+    let image = response.bytes().await.unwrap();
+    let recorded = std::fs::read(fixtures().join(&path)).unwrap();
+    assert_eq!(&image[..], &recorded[..]);
+
+    bridge.join();
+}
+
+#[tokio::test]
+async fn fav_requests_the_submission() {
+    let (standin, bridge) = start().await;
+
+    let key = FavKey::try_from(site_url("/fav/1234/?key=abc")).unwrap();
+    bridge.client().fav(key).await.ok();
+
+    let received = single(&standin);
+    assert!(received.target.starts_with("/fav/1234/"), "{:?}", received);
+    assert_logged_in(&received);
+
+    bridge.join();
+}
+
+#[tokio::test]
+async fn reply_posts_the_text() {
+    let (standin, bridge) = start().await;
+
+    let url = site_url("/replyto/submission/5678/");
+    let key = CommentReplyKey::try_from(url).unwrap();
+    bridge.client().reply(key, "hello".into()).await.ok();
+
+    let received = standin.take_received();
+    let post = received.iter().find(|r| r.method == "POST");
+    let post = post.unwrap_or_else(|| panic!("no POST in {:?}", received));
+
+    assert!(post.target.contains("5678"), "{:?}", post);
+    assert!(post.body.contains("hello"), "{:?}", post);
+    assert_logged_in(post);
+
+    bridge.join();
+}
+
+#[tokio::test]
+async fn clear_submissions_posts_the_ids() {
+    let (standin, bridge) = start().await;
+
+    let keys = ["/view/1234/", "/view/5678/"]
+        .iter()
+        .map(|p| ViewKey::try_from(site_url(p)).unwrap())
+        .collect();
+    bridge.client().clear_submissions(keys).await.ok();
+
+    let received = standin.take_received();
+    let post = received.iter().find(|r| r.method == "POST");
+    let post = post.unwrap_or_else(|| panic!("no POST in {:?}", received));
+
+    assert!(post.target.starts_with("/msg/submissions/"), "{:?}", post);
+    assert!(post.body.contains("1234"), "{:?}", post);
+    assert!(post.body.contains("5678"), "{:?}", post);
+    assert_logged_in(post);
+
+    bridge.join();
+}
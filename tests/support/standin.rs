@@ -0,0 +1,217 @@
+//! Stand-in for FurAffinity, serving recorded pages and images from a
+//! directory so the app can be run end to end without network access.
+//!
+//! The integration tests start one on an ephemeral port. To point the app
+//! at one instead, run the `standin` example with the fixture directory:
+//!
+//! ```sh
+//! cargo run --example standin -- tests/fixtures/ 127.0.0.1:8080
+//! LABRAT_BASE_URL=http://127.0.0.1:8080/ cargo run --bin labrat-gtk
+//! ```
+//!
+//! Pages are looked up by path, with `index.html` standing in for paths
+//! ending in `/`, so `/view/1234/` is served from `view/1234/index.html`.
+//! Images are requested as `/<host>/<path>`, see `Util::http_get`.
+//!
+//! Forms (replying, clearing submissions) are accepted and answered with the
+//! page at the same path if there is one, or a redirect to `/` otherwise.
+//! Every request is logged and kept, so a run can be checked against what
+//! it was expected to send.
+
+// The tests and the example each use a different part of this.
+#![allow(dead_code)]
+
+use reqwest::Url;
+
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request the stand-in answered.
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub method: String,
+    pub target: String,
+    pub cookie: Option<String>,
+    pub body: String,
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// File under `root` for the request path, if the path stays inside it.
+fn resolve(root: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(|c| c == '?' || c == '#').next()?;
+    let mut file = root.to_owned();
+
+    for component in Path::new(path.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(c) => file.push(c),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+
+    if path.ends_with('/') || file.is_dir() {
+        file.push("index.html");
+    }
+
+    Some(file)
+}
+
+async fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await
+}
+
+async fn handle(
+    root: &Path,
+    received: &Mutex<Vec<Received>>,
+    mut stream: TcpStream,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&mut stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let target = parts.next().unwrap_or_default().to_owned();
+
+    let mut length = 0;
+    let mut cookie = None;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        let mut header = line.splitn(2, ':');
+        let name = header.next().unwrap_or_default();
+        let value = header.next().unwrap_or_default().trim();
+
+        if name.eq_ignore_ascii_case("content-length") {
+            length = value.parse().unwrap_or(0);
+        } else if name.eq_ignore_ascii_case("cookie") {
+            cookie = Some(value.to_owned());
+        }
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    eprintln!("{} {} ({} byte body)", method, target, body.len());
+
+    received.lock().unwrap().push(Received {
+        method: method.clone(),
+        target: target.clone(),
+        cookie,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    });
+
+    let file = match resolve(root, &target) {
+        Some(f) => f,
+        None => return respond(&mut stream, "400 Bad Request", &[], b"").await,
+    };
+
+    match tokio::fs::read(&file).await {
+        Ok(contents) => {
+            let headers = [("Content-Type", content_type(&file))];
+            respond(&mut stream, "200 OK", &headers, &contents).await
+        }
+
+        Err(_) if method == "POST" => {
+            let headers = [("Location", "/")];
+            respond(&mut stream, "303 See Other", &headers, b"").await
+        }
+
+        Err(_) => {
+            eprintln!("  no fixture at {}", file.display());
+            respond(&mut stream, "404 Not Found", &[], b"").await
+        }
+    }
+}
+
+/// A stand-in running on the current tokio runtime.
+#[derive(Debug, Clone)]
+pub struct StandIn {
+    address: SocketAddr,
+    received: Arc<Mutex<Vec<Received>>>,
+}
+
+impl StandIn {
+    /// Serve fixtures from `root` on `address`, which can use port zero to
+    /// pick any free port.
+    pub async fn bind(root: PathBuf, address: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address).await?;
+        let address = listener.local_addr()?;
+        let received: Arc<Mutex<Vec<Received>>> = Default::default();
+
+        eprintln!("serving {} on http://{}/", root.display(), address);
+
+        let root = Arc::new(root);
+        let recorded = received.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((s, _)) => s,
+                    Err(e) => {
+                        eprintln!("unable to accept connection: {}", e);
+                        continue;
+                    }
+                };
+
+                let root = root.clone();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle(&root, &recorded, stream).await {
+                        eprintln!("connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { address, received })
+    }
+
+    /// Base URL to point clients at.
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://{}/", self.address)).unwrap()
+    }
+
+    /// Every request answered since the last call.
+    pub fn take_received(&self) -> Vec<Received> {
+        std::mem::take(&mut *self.received.lock().unwrap())
+    }
+}